@@ -0,0 +1,37 @@
+#![no_main]
+
+use chiprust8::chip_core::ChipCore;
+use chiprust8::quirks::Quirks;
+use libfuzzer_sys::fuzz_target;
+
+const MAX_CYCLES: u64 = 5000;
+
+// Input layout: a quirk preset byte, a key event count N, N two-byte key events
+// (cycle / 16, then key in the low nibble and pressed in bit 4), and the ROM.
+fuzz_target!(|data: &[u8]| {
+    let [preset, event_count, rest @ ..] = data else {
+        return;
+    };
+
+    let events_len = (*event_count as usize * 2).min(rest.len());
+    let (events, rom) = rest.split_at(events_len);
+
+    let mut chip = ChipCore::new();
+    chip.set_quirks(Quirks::PRESETS[*preset as usize % Quirks::PRESETS.len()].1);
+    chip.set_write_monitor_enabled(true);
+
+    if !chip.load_rom_bytes(rom) {
+        return;
+    }
+
+    for event in events.chunks_exact(2) {
+        chip.queue_key_event(event[0] as u64 * 16, event[1] & 0xF, event[1] & 0x10 != 0);
+    }
+
+    chip.run_cycles(MAX_CYCLES);
+
+    let mut lores = [0; ChipCore::CHIP_FRAMEBUFFER_SIZE];
+    let mut hires = [0; ChipCore::SCHIP_FRAMEBUFFER_SIZE];
+    chip.render_to_rgb_chip_buffer(&mut lores);
+    chip.render_to_rgb_schip_buffer(&mut hires);
+});
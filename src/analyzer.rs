@@ -0,0 +1,413 @@
+use std::collections::{ BTreeMap, BTreeSet };
+use crate::chip_core::ChipCore;
+use crate::disasm;
+
+// Static analysis of a ROM: follows every reachable path from 0x200 without running it, so
+// code can be told apart from the sprites and tables stored in between.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ByteKind {
+    Code,
+    // Drawn by a DXYN after an ANNN pointed I at it.
+    Sprite,
+    // Everything else in the ROM, referenced or not.
+    Data,
+}
+
+impl ByteKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ByteKind::Code => "code",
+            ByteKind::Sprite => "sprite",
+            ByteKind::Data => "data",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    Fallthrough,
+    Jump,
+    Call,
+    // Taken when a skip instruction skips.
+    Skip,
+    // A BNNN jump table entry.
+    Indirect,
+}
+
+impl Edge {
+    fn name(self) -> &'static str {
+        match self {
+            Edge::Fallthrough => "fallthrough",
+            Edge::Jump => "jump",
+            Edge::Call => "call",
+            Edge::Skip => "skip",
+            Edge::Indirect => "indirect",
+        }
+    }
+}
+
+// A run of instructions that is only ever entered at `start`. `end` is exclusive.
+pub struct Block {
+    pub start: u16,
+    pub end: u16,
+    pub successors: Vec<(u16, Edge)>,
+}
+
+#[derive(Clone, Copy)]
+enum Flow {
+    Next,
+    Stop,
+    Jump(u16),
+    Call(u16),
+    Skip,
+    Indirect(u16),
+}
+
+pub struct Analysis {
+    memory: Vec<u8>,
+    rom_end: usize,
+    instructions: BTreeSet<u16>,
+    blocks: BTreeMap<u16, Block>,
+    subroutines: BTreeMap<u16, BTreeSet<u16>>,
+    indirect_jumps: BTreeMap<u16, Vec<u16>>,
+    data_refs: BTreeSet<u16>,
+    unknown: BTreeSet<u16>,
+    kinds: Vec<ByteKind>,
+}
+
+// Only opcodes the core implements count as code. Anything else runs as a no-op there, but is
+// far more likely to be data, so it ends the path as an unknown opcode.
+fn flow(opcode: u16) -> Option<Flow> {
+    if !ChipCore::is_implemented(opcode) {
+        return None;
+    }
+
+    let nnn = opcode & 0x0FFF;
+    let flow = match opcode & 0xF000 {
+        0x0000 if opcode == 0x00EE => Flow::Stop,
+        0x1000 => Flow::Jump(nnn),
+        0x2000 => Flow::Call(nnn),
+        0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xE000 => Flow::Skip,
+        0xB000 => Flow::Indirect(nnn),
+        _ => Flow::Next,
+    };
+
+    Some(flow)
+}
+
+impl Analysis {
+    pub fn new(rom: &[u8]) -> Self {
+        let rom_end = (0x200 + rom.len()).min(ChipCore::RAM_SIZE);
+        let mut memory = vec![0; ChipCore::RAM_SIZE];
+        memory[0x200..rom_end].copy_from_slice(&rom[..rom_end - 0x200]);
+
+        let mut analysis = Self {
+            memory,
+            rom_end,
+            instructions: BTreeSet::new(),
+            blocks: BTreeMap::new(),
+            subroutines: BTreeMap::new(),
+            indirect_jumps: BTreeMap::new(),
+            data_refs: BTreeSet::new(),
+            unknown: BTreeSet::new(),
+            kinds: Vec::new(),
+        };
+
+        let (leaders, sprites) = analysis.trace();
+        analysis.build_blocks(&leaders);
+        analysis.classify(&sprites);
+        analysis
+    }
+
+    fn in_rom(&self, addr: u16) -> bool {
+        (0x200..self.rom_end).contains(&(addr as usize))
+    }
+
+    fn word(&self, addr: u16) -> u16 {
+        ((self.memory[addr as usize & 0xFFF] as u16) << 8) | self.memory[(addr as usize + 1) & 0xFFF] as u16
+    }
+
+    // Walks every path from 0x200 and returns the block leaders and the sprites drawn on the way.
+    // I is only tracked within a straight run of instructions, which covers the usual ANNN, DXYN.
+    fn trace(&mut self) -> (BTreeSet<u16>, Vec<(u16, u16)>) {
+        let mut pending = vec![0x200];
+        let mut leaders = BTreeSet::from([0x200]);
+        let mut sprites = Vec::new();
+
+        while let Some(mut addr) = pending.pop() {
+            let mut index = None;
+
+            while self.in_rom(addr) && self.in_rom(addr + 1) && !self.instructions.contains(&addr) {
+                let opcode = self.word(addr);
+                let Some(flow) = flow(opcode) else {
+                    self.unknown.insert(addr);
+                    break;
+                };
+
+                self.instructions.insert(addr);
+
+                match opcode & 0xF000 {
+                    0xA000 => {
+                        index = Some(opcode & 0x0FFF);
+                        self.data_refs.insert(opcode & 0x0FFF);
+                    }
+                    0xD000 => {
+                        if let Some(index) = index {
+                            let rows = opcode & 0x000F;
+                            sprites.push((index, if rows == 0 { 32 } else { rows }));
+                        }
+                    }
+                    0xF000 if opcode & 0x00FF == 0x1E || opcode & 0x00FF == 0x29 || opcode & 0x00FF == 0x30 => {
+                        index = None;
+                    }
+                    _ => {}
+                }
+
+                let next = addr + 2;
+                match flow {
+                    Flow::Next => {}
+                    Flow::Stop => break,
+                    Flow::Jump(target) => {
+                        leaders.insert(target);
+                        pending.push(target);
+                        break;
+                    }
+                    Flow::Call(target) => {
+                        self.subroutines.entry(target).or_default().insert(addr);
+                        leaders.extend([target, next]);
+                        pending.push(target);
+                    }
+                    Flow::Skip => {
+                        leaders.extend([next, next + 2]);
+                        pending.push(next + 2);
+                    }
+                    Flow::Indirect(table) => {
+                        // V0 is unknown, so assume the common jump table: NNN and every 1NNN right after it.
+                        let mut targets = vec![table];
+                        if self.in_rom(table) && self.word(table) & 0xF000 == 0x1000 {
+                            let mut entry = table + 2;
+                            while self.in_rom(entry) && self.word(entry) & 0xF000 == 0x1000 {
+                                targets.push(entry);
+                                entry += 2;
+                            }
+                        }
+
+                        leaders.extend(targets.iter().copied());
+                        pending.extend(targets.iter().copied());
+                        self.indirect_jumps.insert(addr, targets);
+                        break;
+                    }
+                }
+
+                addr = next;
+            }
+        }
+
+        (leaders, sprites)
+    }
+
+    fn build_blocks(&mut self, leaders: &BTreeSet<u16>) {
+        for &start in leaders.iter().filter(|addr| self.instructions.contains(addr)) {
+            let mut addr = start;
+
+            let successors = loop {
+                let next = addr + 2;
+
+                match flow(self.word(addr)).unwrap_or(Flow::Stop) {
+                    Flow::Next if self.instructions.contains(&next) && !leaders.contains(&next) => {
+                        addr = next;
+                        continue;
+                    }
+                    Flow::Next => break vec![(next, Edge::Fallthrough)],
+                    Flow::Stop => break Vec::new(),
+                    Flow::Jump(target) => break vec![(target, Edge::Jump)],
+                    Flow::Call(target) => break vec![(target, Edge::Call), (next, Edge::Fallthrough)],
+                    Flow::Skip => break vec![(next, Edge::Fallthrough), (next + 2, Edge::Skip)],
+                    Flow::Indirect(_) => {
+                        break self.indirect_jumps[&addr].iter().map(|&target| (target, Edge::Indirect)).collect();
+                    }
+                }
+            };
+
+            let successors = successors.into_iter().filter(|(target, _)| self.instructions.contains(target)).collect();
+            self.blocks.insert(start, Block { start, end: addr + 2, successors });
+        }
+    }
+
+    fn classify(&mut self, sprites: &[(u16, u16)]) {
+        self.kinds = (0x200..self.rom_end as u16)
+            .map(|addr| {
+                if self.instructions.contains(&addr) || self.instructions.contains(&(addr - 1)) {
+                    ByteKind::Code
+                }
+                else if sprites.iter().any(|&(start, rows)| (start..start + rows).contains(&addr)) {
+                    ByteKind::Sprite
+                }
+                else {
+                    ByteKind::Data
+                }
+            })
+            .collect();
+    }
+
+    // Kind of a ROM byte, or None outside the ROM.
+    pub fn kind(&self, addr: u16) -> Option<ByteKind> {
+        self.in_rom(addr).then(|| self.kinds[addr as usize - 0x200])
+    }
+
+    pub fn is_instruction(&self, addr: u16) -> bool {
+        self.instructions.contains(&addr)
+    }
+
+    // Every reachable instruction as (address, opcode).
+    pub fn instructions(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.instructions.iter().map(|&addr| (addr, self.word(addr)))
+    }
+
+    // Reachable opcodes the core doesn't implement, such as XO-CHIP extensions or 0NNN
+    // machine code calls. Analysis of that path stops there.
+    pub fn unknown_opcodes(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.unknown.iter().map(|&addr| (addr, self.word(addr)))
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.values()
+    }
+
+    // Subroutine entry points with the addresses that call them.
+    pub fn subroutines(&self) -> &BTreeMap<u16, BTreeSet<u16>> {
+        &self.subroutines
+    }
+
+    fn regions(&self) -> Vec<(u16, u16, ByteKind)> {
+        let mut regions: Vec<(u16, u16, ByteKind)> = Vec::new();
+
+        for (offset, &kind) in self.kinds.iter().enumerate() {
+            let addr = 0x200 + offset as u16;
+            match regions.last_mut() {
+                Some((_, end, last)) if *last == kind => *end = addr,
+                _ => regions.push((addr, addr, kind)),
+            }
+        }
+
+        regions
+    }
+
+    fn label(&self, addr: u16) -> Option<String> {
+        if self.subroutines.contains_key(&addr) {
+            Some(format!("sub_{:03X}", addr))
+        }
+        else if self.blocks.contains_key(&addr) {
+            Some(format!("L{:03X}", addr))
+        }
+        else if self.data_refs.contains(&addr) {
+            Some(format!("{}_{:03X}", self.kind(addr)?.name(), addr))
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let count = |kind| self.kinds.iter().filter(|&&k| k == kind).count();
+        let mut report = format!("ROM {:03X}-{:03X}: {} code bytes, {} sprite bytes, {} data bytes\n",
+            0x200, self.rom_end.saturating_sub(1), count(ByteKind::Code), count(ByteKind::Sprite), count(ByteKind::Data));
+
+        report += "\nSubroutines:\n";
+        for (addr, callers) in &self.subroutines {
+            let callers: Vec<String> = callers.iter().map(|caller| format!("{:03X}", caller)).collect();
+            report += &format!("  {:03X} called from {}\n", addr, callers.join(", "));
+        }
+
+        if !self.indirect_jumps.is_empty() {
+            report += "\nIndirect jumps:\n";
+            for (addr, targets) in &self.indirect_jumps {
+                let targets: Vec<String> = targets.iter().map(|target| format!("{:03X}", target)).collect();
+                report += &format!("  {:03X} {} -> {}\n", addr, disasm::disassemble(self.word(*addr)), targets.join(", "));
+            }
+        }
+
+        if !self.unknown.is_empty() {
+            report += "\nUnknown opcodes:\n";
+            for (addr, opcode) in self.unknown_opcodes() {
+                report += &format!("  {:03X} {:04X}\n", addr, opcode);
+            }
+        }
+
+        report += "\nRegions:\n";
+        for (start, end, kind) in self.regions() {
+            report += &format!("  {:03X}-{:03X} {}\n", start, end, kind.name());
+        }
+
+        report += "\nControl flow graph:\n";
+        for block in self.blocks.values() {
+            let successors: Vec<String> = block.successors.iter().map(|(target, edge)| format!("{:03X} ({})", target, edge.name())).collect();
+            report += &format!("  {:03X}-{:03X} -> {}\n", block.start, block.end - 1, if successors.is_empty() { "none".to_string() } else { successors.join(", ") });
+        }
+
+        report
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph rom {\n    node [shape=box fontname=\"monospace\"];\n");
+
+        for block in self.blocks.values() {
+            let mut label = self.label(block.start).map_or(String::new(), |label| label + ":\\l");
+            for addr in (block.start..block.end).step_by(2) {
+                label += &format!("{:03X}: {}\\l", addr, disasm::disassemble(self.word(addr)));
+            }
+
+            dot += &format!("    b{:03X} [label=\"{}\"];\n", block.start, label);
+        }
+
+        for block in self.blocks.values() {
+            for (target, edge) in &block.successors {
+                let style = match edge {
+                    Edge::Fallthrough | Edge::Jump => "",
+                    Edge::Call => " [style=dashed label=\"call\"]",
+                    Edge::Skip => " [label=\"skip\"]",
+                    Edge::Indirect => " [style=dotted]",
+                };
+                dot += &format!("    b{:03X} -> b{:03X}{};\n", block.start, target, style);
+            }
+        }
+
+        dot + "}\n"
+    }
+
+    // Disassembly that decodes only reachable code and prints everything else as bytes,
+    // with sprite rows drawn out.
+    pub fn listing(&self) -> String {
+        let mut listing = String::new();
+        let mut addr = 0x200;
+
+        while (addr as usize) < self.rom_end {
+            if let Some(label) = self.label(addr) {
+                listing += &format!("{}:\n", label);
+            }
+
+            if self.is_instruction(addr) && self.in_rom(addr + 1) {
+                let opcode = self.word(addr);
+                listing += &format!("  {:03X}  {:04X}  {}\n", addr, opcode, disasm::disassemble(opcode));
+                addr += 2;
+                continue;
+            }
+
+            let byte = self.memory[addr as usize];
+            if self.kind(addr) == Some(ByteKind::Sprite) {
+                let row: String = (0..8).rev().map(|bit| if byte >> bit & 1 == 1 { '#' } else { '.' }).collect();
+                listing += &format!("  {:03X}  {:02X}    DB {:02X} ; {}\n", addr, byte, byte, row);
+            }
+            else {
+                listing += &format!("  {:03X}  {:02X}    DB {:02X}\n", addr, byte, byte);
+            }
+            addr += 1;
+        }
+
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests;
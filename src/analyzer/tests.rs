@@ -0,0 +1,77 @@
+use super::*;
+
+fn rom(words: &[u16]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_be_bytes()).collect()
+}
+
+// 200: LD I, 20E   202: DRW V0, V1, 2   204: CALL 208   206: JP 206
+// 208: SE V0, 01   20A: RET   20C: RET   20E: sprite   210: data
+fn sprite_rom() -> Vec<u8> {
+    rom(&[0xA20E, 0xD012, 0x2208, 0x1206, 0x3001, 0x00EE, 0x00EE, 0x3C7E, 0xFF00])
+}
+
+#[test]
+fn separates_code_sprites_and_data() {
+    let analysis = Analysis::new(&sprite_rom());
+
+    assert!((0x200..0x20E).all(|addr| analysis.kind(addr) == Some(ByteKind::Code)));
+    assert_eq!((analysis.kind(0x20E), analysis.kind(0x20F)), (Some(ByteKind::Sprite), Some(ByteKind::Sprite)));
+    assert_eq!((analysis.kind(0x210), analysis.kind(0x211)), (Some(ByteKind::Data), Some(ByteKind::Data)));
+    assert_eq!(analysis.kind(0x212), None);
+    assert!(!analysis.is_instruction(0x20E), "sprite data must not be decoded as SE VC, 7E");
+}
+
+#[test]
+fn builds_control_flow_graph_and_subroutines() {
+    let analysis = Analysis::new(&sprite_rom());
+
+    let ranges: Vec<(u16, u16)> = analysis.blocks().map(|block| (block.start, block.end)).collect();
+    assert_eq!(ranges, [(0x200, 0x206), (0x206, 0x208), (0x208, 0x20A), (0x20A, 0x20C), (0x20C, 0x20E)]);
+
+    let successors: Vec<&[(u16, Edge)]> = analysis.blocks().map(|block| &block.successors[..]).collect();
+    assert_eq!(successors, [
+        &[(0x208, Edge::Call), (0x206, Edge::Fallthrough)][..],
+        &[(0x206, Edge::Jump)],
+        &[(0x20A, Edge::Fallthrough), (0x20C, Edge::Skip)],
+        &[],
+        &[],
+    ]);
+
+    assert_eq!(analysis.subroutines().keys().copied().collect::<Vec<_>>(), [0x208]);
+    assert!(analysis.subroutines()[&0x208].contains(&0x204));
+}
+
+#[test]
+fn follows_jump_tables() {
+    // 200: LD V0, 02   202: JP V0, 206   204: data   206: JP 20A   208: JP 20C   20A/20C: RET
+    let analysis = Analysis::new(&rom(&[0x6002, 0xB206, 0xFFFF, 0x120A, 0x120C, 0x00EE, 0x00EE]));
+
+    let entry = analysis.blocks().next().unwrap();
+    assert_eq!(entry.successors, [(0x206, Edge::Indirect), (0x208, Edge::Indirect)]);
+    assert!(analysis.is_instruction(0x20A) && analysis.is_instruction(0x20C));
+    assert_eq!(analysis.kind(0x204), Some(ByteKind::Data));
+}
+
+#[test]
+fn exports_listing_and_dot() {
+    let analysis = Analysis::new(&sprite_rom());
+
+    let listing = analysis.listing();
+    assert!(listing.contains("sub_208:\n  208  3001  SE V0, 01\n"));
+    assert!(listing.contains("sprite_20E:\n  20E  3C    DB 3C ; ..####..\n"));
+    assert!(!listing.contains("SE VC"));
+
+    let dot = analysis.to_dot();
+    assert!(dot.starts_with("digraph rom {"));
+    assert!(dot.contains("b200 -> b208 [style=dashed label=\"call\"];"));
+}
+
+#[test]
+fn unimplemented_opcodes_end_the_path() {
+    // 00FD is SuperChip's exit, which the core runs as a no-op.
+    let analysis = Analysis::new(&rom(&[0x6001, 0x00FD, 0x6002]));
+
+    assert_eq!(analysis.instructions().collect::<Vec<_>>(), [(0x200, 0x6001)]);
+    assert_eq!(analysis.unknown_opcodes().collect::<Vec<_>>(), [(0x202, 0x00FD)]);
+    assert!(!analysis.is_instruction(0x204));
+}
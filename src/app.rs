@@ -1,131 +1,965 @@
-use crate::chip_core::{ ChipCore };
-use std::path::PathBuf;
+use crate::chip_core::{ ChipCore, KeyWait };
+use crate::tracer::Tracer;
+use crate::write_monitor::WriteMonitor;
+use crate::memory_viewer::MemoryViewer;
+use crate::sprite_viewer::SpriteViewer;
+use crate::octo;
+use crate::rom_watcher::{ self, WatchedRom };
+use crate::capture::{ self, GifRecorder };
+use crate::filters::{ Filter, FilterChain };
+use crate::pacer::FramePacer;
+use crate::osd::{ Osd, Stats };
+use crate::keypad::Keypad;
+use crate::heat_map::HeatMap;
+use crate::input::KeyEventQueue;
+use crate::quirks::Quirks;
+use crate::platform::{ Detection, Platform };
+use crate::macros::{ Autofire, InputMacro, MacroPlayback, MacroRecorder };
+use std::fs;
+use std::path::{ Path, PathBuf };
 use std::time::{ Duration, Instant };
-use minifb::{ Key, KeyRepeat, Menu, Window, WindowOptions };
+use minifb::{ Key, KeyRepeat, Menu, MouseButton, MouseMode, Window, WindowOptions };
 
 pub struct App {
     chip: ChipCore,
     chip_screen_buf: [u32; ChipCore::CHIP_FRAMEBUFFER_SIZE],
     schip_screen_buf: [u32; ChipCore::SCHIP_FRAMEBUFFER_SIZE],
     window: Window,
+    frame_buf: Vec<u32>,
+    fullscreen: bool,
+    screen_size: (usize, usize),
+    windowed_pos: (isize, isize),
+    display_scale: Option<usize>,
+    scale_menu: Menu,
+    filters: FilterChain,
+    filter_menu: Menu,
+    heat_map: HeatMap,
+    memory_viewer: Option<MemoryViewer>,
+    sprite_viewer: Option<SpriteViewer>,
     options_menu : Menu,
     file_menu : Menu,
-    rom_path: PathBuf,
+    debug_menu : Menu,
+    rom: WatchedRom,
     rom_loaded: bool,
+    labels: Vec<(String, u16)>,
+    keep_settings_on_reload: bool,
+    osd: Osd,
+    keypad: Keypad,
+    mouse_key: Option<u8>,
+    key_events: KeyEventQueue,
+    last_input_time: Instant,
+    keyboard_keys: [bool; 16],
+    queued_keys: [bool; 16],
+    autofire: Autofire,
+    macro_recorder: Option<MacroRecorder>,
+    input_macro: Option<InputMacro>,
+    macro_playback: Option<MacroPlayback>,
+    macro_keys: [bool; 16],
+    recorder: Option<GifRecorder>,
+    capture_scale: usize,
+    capture_scale_menu: Menu,
+    pacer: FramePacer,
     chip_paused: bool,
-    ipf: u32,
+    speed_index: usize,
+    speed_menu: Menu,
+    quirks_menu: Menu,
+    platform: Option<Platform>,
+    platform_override: Option<Platform>,
+    platform_menu: Menu,
     execute_times: f64,
     execute_count: u32,
+    presented_frames: u32,
+    last_cycle_count: u64,
     seconds_timer: Instant
 }
 
 impl App {
     pub const APP_NAME: &'static str = "ChipRust8";
     const WINDOW_SCALE: usize = 12;
+    const CYCLE_RATE_STEP: u32 = 60;
+    const CYCLE_RATE_FAST_STEP: u32 = 100 * Self::CYCLE_RATE_STEP;
+    const MAX_CYCLE_RATE: u32 = 100000 * Self::CYCLE_RATE_STEP;
+    const TICK_RATE: u32 = 60;
     const FILE_MENU_LOAD_ID: usize = 1;
     const FILE_MENU_RELOAD_ID: usize = 2;
+    const DEBUG_MENU_HEAT_MAP_ID: usize = 3;
+    const DEBUG_MENU_EXPORT_COVERAGE_ID: usize = 4;
+    const DEBUG_MENU_PROFILER_ID: usize = 5;
+    const DEBUG_MENU_MEMORY_VIEWER_ID: usize = 6;
+    const DEBUG_MENU_EXPORT_RAM_ID: usize = 7;
+    const DEBUG_MENU_SPRITE_VIEWER_ID: usize = 8;
+    const OPTIONS_MENU_WATCH_ID: usize = 9;
+    const OPTIONS_MENU_KEEP_SETTINGS_ID: usize = 10;
+    const FILE_MENU_SCREENSHOT_ID: usize = 11;
+    const FILE_MENU_RECORD_ID: usize = 12;
+    const OPTIONS_MENU_FULLSCREEN_ID: usize = 13;
+    const OPTIONS_MENU_STATS_ID: usize = 14;
+    const OPTIONS_MENU_KEYPAD_ID: usize = 15;
+    const FILE_MENU_SOFT_RESET_ID: usize = 16;
+    const FILE_MENU_HARD_RESET_ID: usize = 17;
+    const DEBUG_MENU_WRITE_WARNINGS_ID: usize = 18;
+    const DEBUG_MENU_PROFILER_REPORT_ID: usize = 19;
+    const CAPTURE_SCALE_MENU_BASE_ID: usize = 100;
+    const CAPTURE_SCALES: [usize; 4] = [1, 2, 4, 8];
+    const SCALE_MENU_AUTO_ID: usize = 200;
+    const SCALE_MENU_BASE_ID: usize = 201;
+    const DISPLAY_SCALES: [usize; 7] = [2, 4, 6, 8, 12, 16, 20];
+    const DEFAULT_SCREEN_SIZE: (usize, usize) = (1920, 1080);
+    const FILTER_MENU_BASE_ID: usize = 300;
+    const FILTER_KEYS: [Key; 4] = [Key::F1, Key::F2, Key::F3, Key::F4];
+    const SPEED_MENU_BASE_ID: usize = 400;
+    const SPEED_PRESETS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+    const NORMAL_SPEED_INDEX: usize = 2;
+    const TURBO_SPEED: f64 = 8.0;
+    const QUIRKS_MENU_BASE_ID: usize = 500;
+    const PLATFORM_MENU_AUTO_ID: usize = 600;
+    const PLATFORM_MENU_BASE_ID: usize = 601;
     const KEY_BINDING: [Key; 16] = [
         Key::X, Key::Key1, Key::Key2, Key::Key3, Key::Q, Key::W, Key::E, Key::A,
         Key::S, Key::D, Key::Z, Key::C, Key::Key4, Key::R, Key::F, Key::V,
     ];
 
-    fn update_chip_input(&mut self) {
-        let ipf_step = if self.window.is_key_down(Key::RightShift) || self.window.is_key_down(Key::LeftShift) { 100000 } else { 1 };
+    fn update_chip_input(&mut self, frame_duration: Duration) {
+        let rate_step = if self.window.is_key_down(Key::RightShift) || self.window.is_key_down(Key::LeftShift) { Self::CYCLE_RATE_FAST_STEP } else { Self::CYCLE_RATE_STEP };
+        let rate = self.chip.cycle_rate();
 
-        if self.window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
-            self.ipf += ipf_step;
+        if self.window.is_key_pressed(Key::Right, KeyRepeat::Yes) && rate < Self::MAX_CYCLE_RATE {
+            self.chip.set_cycle_rate(rate.saturating_add(rate_step).min(Self::MAX_CYCLE_RATE));
             self.update_window_title();
         }
-        else if self.window.is_key_pressed(Key::Left, KeyRepeat::Yes) && self.ipf > ipf_step {
-            self.ipf -= ipf_step;
+        else if self.window.is_key_pressed(Key::Left, KeyRepeat::Yes) && rate > Self::CYCLE_RATE_STEP {
+            self.chip.set_cycle_rate(rate.saturating_sub(rate_step).max(Self::CYCLE_RATE_STEP));
             self.update_window_title();
         }
 
+        // Key events that arrived since the last frame are spread over this frame's cycles in
+        // proportion to when they arrived, so their order and spacing survive.
+        let now = Instant::now();
+        let span = now.duration_since(self.last_input_time).as_secs_f64().max(f64::EPSILON);
+        let start_cycle = self.chip.cycle_count();
+        let frame_cycles = frame_duration.as_secs_f64() * self.chip.cycle_rate() as f64;
+
+        for (time, key, pressed) in self.key_events.drain() {
+            let Some(i) = Self::KEY_BINDING.iter().position(|&binding| binding == key) else {
+                continue;
+            };
+
+            let offset = time.saturating_duration_since(self.last_input_time).as_secs_f64() / span;
+            self.keyboard_keys[i] = pressed;
+            self.queue_key(i, start_cycle + (offset.min(1.0) * frame_cycles) as u64);
+        }
+        self.last_input_time = now;
+
+        // Catch anything the callback missed, e.g. a release while the window was unfocused.
+        let end_cycle = start_cycle + frame_cycles as u64;
         for i in 0..16 {
-            let new_key_state = self.window.is_key_down(Self::KEY_BINDING[i]);
+            self.keyboard_keys[i] = self.window.is_key_down(Self::KEY_BINDING[i]);
+            self.queue_key(i, end_cycle);
+        }
+
+        for cycle in self.autofire.toggle_points(start_cycle, end_cycle, self.chip.cycle_rate()) {
+            for i in 0..16 {
+                if self.autofire.is_enabled(i) {
+                    self.queue_key(i, cycle);
+                }
+            }
+        }
+
+        if let (Some(playback), Some(input_macro)) = (&mut self.macro_playback, &self.input_macro) {
+            let due = playback.due(input_macro, end_cycle);
+            if playback.is_finished(input_macro) {
+                self.macro_playback = None;
+            }
 
-            if self.chip.get_keys()[i] != new_key_state {
-                self.chip.key_event(i as u8, new_key_state);
+            for (cycle, key, pressed) in due {
+                self.macro_keys[key as usize] = pressed;
+                self.queue_key(key as usize, cycle);
             }
         }
     }
 
+    // Combines every input source for `key` and queues a change into the core if the result
+    // differs from what was queued last.
+    fn queue_key(&mut self, key: usize, cycle: u64) {
+        let manual = self.keyboard_keys[key] || self.mouse_key == Some(key as u8);
+
+        if let Some(recorder) = &mut self.macro_recorder {
+            recorder.record(cycle, key as u8, manual);
+        }
+
+        let held = manual && (!self.autofire.is_enabled(key) || self.autofire.is_down_phase(cycle, self.chip.cycle_rate()));
+        let pressed = held || self.macro_keys[key];
+
+        if self.queued_keys[key] != pressed {
+            self.queued_keys[key] = pressed;
+            self.chip.queue_key_event(cycle, key as u8, pressed);
+        }
+    }
+
     pub fn new() -> Self {
         let mut app = Self {
             chip: ChipCore::new(),
             chip_screen_buf: [0; ChipCore::CHIP_FRAMEBUFFER_SIZE],
             schip_screen_buf: [0; ChipCore::SCHIP_FRAMEBUFFER_SIZE],
 
-            window: Window::new(Self::APP_NAME, ChipCore::CHIP_SCR_WIDTH * App::WINDOW_SCALE,
-                                ChipCore::CHIP_SCR_HEIGHT * App::WINDOW_SCALE, WindowOptions::default())
-                .unwrap_or_else(|e| {
-                    panic!("{}", e);
-                }),
+            window: Self::create_window(ChipCore::CHIP_SCR_WIDTH * App::WINDOW_SCALE, ChipCore::CHIP_SCR_HEIGHT * App::WINDOW_SCALE, false),
+            frame_buf: Vec::new(),
+            fullscreen: false,
+            screen_size: Self::DEFAULT_SCREEN_SIZE,
+            windowed_pos: (0, 0),
+            display_scale: None,
+            scale_menu: Menu::new("Scale").unwrap(),
+            filters: FilterChain::default(),
+            filter_menu: Menu::new("Filters").unwrap(),
+            heat_map: HeatMap::new(),
+            memory_viewer: None,
+            sprite_viewer: None,
 
             options_menu: Menu::new("Options").unwrap(),
             file_menu: Menu::new("File").unwrap(),
+            debug_menu: Menu::new("Debug").unwrap(),
 
-            rom_path: std::env::current_dir().unwrap(),
+            rom: WatchedRom::new(std::env::current_dir().unwrap()),
             rom_loaded: false,
+            labels: Vec::new(),
+            keep_settings_on_reload: true,
+            osd: Osd::new(),
+            keypad: Keypad::new(),
+            mouse_key: None,
+            key_events: KeyEventQueue::default(),
+            last_input_time: Instant::now(),
+            keyboard_keys: [false; 16],
+            queued_keys: [false; 16],
+            autofire: Autofire::new(),
+            macro_recorder: None,
+            input_macro: None,
+            macro_playback: None,
+            macro_keys: [false; 16],
+            recorder: None,
+            capture_scale: 4,
+            capture_scale_menu: Menu::new("Capture Scale").unwrap(),
+            pacer: FramePacer::new(Self::TICK_RATE),
             chip_paused: false,
-            ipf: 11,
+            speed_index: Self::NORMAL_SPEED_INDEX,
+            speed_menu: Menu::new("Speed").unwrap(),
+            quirks_menu: Menu::new("Quirks").unwrap(),
+            platform: None,
+            platform_override: None,
+            platform_menu: Menu::new("Platform").unwrap(),
             execute_times: 0.0,
             execute_count: 0,
+            presented_frames: 0,
+            last_cycle_count: 0,
             seconds_timer: Instant::now(),
         };
 
         app.file_menu.add_item("Load", Self::FILE_MENU_LOAD_ID).build();
         app.file_menu.add_item("Reload", Self::FILE_MENU_RELOAD_ID).build();
+        app.file_menu.add_item("Soft Reset (F5)", Self::FILE_MENU_SOFT_RESET_ID).build();
+        app.file_menu.add_item("Hard Reset (Shift+F5)", Self::FILE_MENU_HARD_RESET_ID).build();
+        app.file_menu.add_item("Screenshot (F12)", Self::FILE_MENU_SCREENSHOT_ID).build();
+        app.file_menu.add_item("Start/Stop Recording (Shift+F12)", Self::FILE_MENU_RECORD_ID).build();
 
-        app.window.add_menu(&app.file_menu);
-        app.window.add_menu(&app.options_menu);
+        for (i, scale) in Self::CAPTURE_SCALES.iter().enumerate() {
+            app.capture_scale_menu.add_item(&format!("{}x", scale), Self::CAPTURE_SCALE_MENU_BASE_ID + i).build();
+        }
+        app.options_menu.add_sub_menu("Capture Scale", &app.capture_scale_menu);
 
-        app.window.set_target_fps(60);
+        app.scale_menu.add_item("Auto", Self::SCALE_MENU_AUTO_ID).build();
+        for (i, scale) in Self::DISPLAY_SCALES.iter().enumerate() {
+            app.scale_menu.add_item(&format!("{}x", scale), Self::SCALE_MENU_BASE_ID + i).build();
+        }
+        app.options_menu.add_sub_menu("Scale", &app.scale_menu);
+        app.options_menu.add_item("Toggle Fullscreen (F11)", Self::OPTIONS_MENU_FULLSCREEN_ID).build();
+        app.options_menu.add_item("Show Stats (F6)", Self::OPTIONS_MENU_STATS_ID).build();
+        app.options_menu.add_item("Show Keypad (F7)", Self::OPTIONS_MENU_KEYPAD_ID).build();
+
+        for (i, filter) in Filter::ALL.iter().enumerate() {
+            app.filter_menu.add_item(&format!("{} (F{})", filter.name(), i + 1), Self::FILTER_MENU_BASE_ID + i).build();
+        }
+        app.options_menu.add_sub_menu("Filters", &app.filter_menu);
+
+        for (i, speed) in Self::SPEED_PRESETS.iter().enumerate() {
+            app.speed_menu.add_item(&format!("{}x", speed), Self::SPEED_MENU_BASE_ID + i).build();
+        }
+        app.options_menu.add_sub_menu("Speed (-/=)", &app.speed_menu);
+
+        for (i, (name, _)) in Quirks::PRESETS.iter().enumerate() {
+            app.quirks_menu.add_item(name, Self::QUIRKS_MENU_BASE_ID + i).build();
+        }
+        app.options_menu.add_sub_menu("Quirks", &app.quirks_menu);
+
+        app.platform_menu.add_item("Auto", Self::PLATFORM_MENU_AUTO_ID).build();
+        for (i, platform) in Platform::ALL.iter().enumerate() {
+            app.platform_menu.add_item(platform.name(), Self::PLATFORM_MENU_BASE_ID + i).build();
+        }
+        app.options_menu.add_sub_menu("Platform", &app.platform_menu);
+
+        app.options_menu.add_item("Watch ROM", Self::OPTIONS_MENU_WATCH_ID).build();
+        app.options_menu.add_item("Keep Settings On Reload", Self::OPTIONS_MENU_KEEP_SETTINGS_ID).build();
+
+        app.debug_menu.add_item("Coverage Heat Map", Self::DEBUG_MENU_HEAT_MAP_ID).build();
+        app.debug_menu.add_item("Export Coverage Report", Self::DEBUG_MENU_EXPORT_COVERAGE_ID).build();
+        app.debug_menu.add_item("Toggle Profiler", Self::DEBUG_MENU_PROFILER_ID).build();
+        app.debug_menu.add_item("Print Profiler Report", Self::DEBUG_MENU_PROFILER_REPORT_ID).build();
+        app.debug_menu.add_item("Memory Viewer", Self::DEBUG_MENU_MEMORY_VIEWER_ID).build();
+        app.debug_menu.add_item("Export RAM", Self::DEBUG_MENU_EXPORT_RAM_ID).build();
+        app.debug_menu.add_item("Sprite Viewer", Self::DEBUG_MENU_SPRITE_VIEWER_ID).build();
+        app.debug_menu.add_item("Toggle Write Warnings", Self::DEBUG_MENU_WRITE_WARNINGS_ID).build();
+
+        app.setup_window();
         app
     }
 
+    fn create_window(width: usize, height: usize, fullscreen: bool) -> Window {
+        let options = WindowOptions {
+            resize: !fullscreen,
+            borderless: fullscreen,
+            topmost: fullscreen,
+            ..WindowOptions::default()
+        };
+
+        let mut window = Window::new(Self::APP_NAME, width, height, options).unwrap_or_else(|e| {
+            panic!("{}", e);
+        });
+
+        // Pacing is done by FramePacer, so minifb must not sleep on its own.
+        window.set_target_fps(0);
+        window
+    }
+
+    fn setup_window(&mut self) {
+        self.window.set_input_callback(Box::new(self.key_events.clone()));
+        self.window.add_menu(&self.file_menu);
+        self.window.add_menu(&self.options_menu);
+        self.window.add_menu(&self.debug_menu);
+    }
+
+    // minifb has no native fullscreen or monitor size query, so fullscreen is a borderless
+    // topmost window covering `screen_size` (configurable with --screen-size).
+    fn toggle_fullscreen(&mut self) {
+        self.fullscreen = !self.fullscreen;
+
+        let (width, height) = if self.fullscreen {
+            self.windowed_pos = self.window.get_position();
+            self.screen_size
+        }
+        else {
+            self.windowed_size()
+        };
+
+        let (x, y) = if self.fullscreen { (0, 0) } else { self.windowed_pos };
+
+        self.window = Self::create_window(width, height, self.fullscreen);
+        self.window.set_position(x, y);
+        self.setup_window();
+        self.update_window_title();
+    }
+
+    fn windowed_size(&self) -> (usize, usize) {
+        let scale = self.display_scale.unwrap_or(Self::WINDOW_SCALE);
+        (ChipCore::CHIP_SCR_WIDTH * scale, ChipCore::CHIP_SCR_HEIGHT * scale)
+    }
+
+    fn set_display_scale(&mut self, scale: Option<usize>) {
+        self.display_scale = scale;
+
+        if !self.fullscreen {
+            let (width, height) = self.windowed_size();
+            let pos = self.window.get_position();
+
+            self.window = Self::create_window(width, height, false);
+            self.window.set_position(pos.0, pos.1);
+            self.setup_window();
+            self.update_window_title();
+        }
+    }
+
+    pub fn set_screen_size(&mut self, width: usize, height: usize) {
+        self.screen_size = (width, height);
+    }
+
+    pub fn toggle_filter(&mut self, filter: Filter) {
+        self.filters.toggle(filter);
+        let state = if self.filters.is_enabled(filter) { "on" } else { "off" };
+        self.notify(&format!("{} {}", filter.name(), state));
+    }
+
+    // Integer-scales the current frame into the window through the filter chain and
+    // letterboxes the rest. Scale is counted in hires pixels, so lores and hires images end
+    // up the same physical size.
+    fn present(&mut self) {
+        let (win_width, win_height) = self.window.get_size();
+        let (pixels, width, height): (&[u32], usize, usize) = if self.chip.high_res_mode() {
+            (&self.schip_screen_buf, ChipCore::SCHIP_SCR_WIDTH, ChipCore::SCHIP_SCR_HEIGHT)
+        }
+        else {
+            (&self.chip_screen_buf, ChipCore::CHIP_SCR_WIDTH, ChipCore::CHIP_SCR_HEIGHT)
+        };
+
+        let max_scale = (win_width / ChipCore::SCHIP_SCR_WIDTH).min(win_height / ChipCore::SCHIP_SCR_HEIGHT).max(1);
+        let hires_scale = self.display_scale.map_or(max_scale, |scale| (scale / 2).clamp(1, max_scale));
+        let pixel_size = hires_scale * ChipCore::SCHIP_SCR_WIDTH / width;
+
+        let image = self.filters.apply(pixels, width, height, pixel_size);
+        let out_width = image.width.min(win_width);
+        let out_height = image.height.min(win_height);
+        let offset_x = (win_width - out_width) / 2;
+        let offset_y = (win_height - out_height) / 2;
+
+        self.frame_buf.clear();
+        self.frame_buf.resize(win_width * win_height, 0);
+
+        for y in 0..out_height {
+            let src_row = &image.pixels[y * image.width..][..out_width];
+            self.frame_buf[(offset_y + y) * win_width + offset_x..][..out_width].copy_from_slice(src_row);
+        }
+
+        self.heat_map.draw(&mut self.frame_buf, win_width, win_height, self.chip.coverage());
+
+        let key_hints = Self::KEY_BINDING.map(|key| format!("{:?}", key).trim_start_matches("Key").to_string());
+        self.keypad.draw(&mut self.frame_buf, win_width, win_height, self.chip.get_keys(), &key_hints);
+        self.osd.set_sound_active(self.chip.is_sound_active());
+        self.osd.set_key_wait(match self.chip.key_wait() {
+            KeyWait::WaitingForPress { reg } | KeyWait::WaitingForRelease { reg, .. } => Some(reg),
+            _ => None,
+        });
+        self.osd.draw(&mut self.frame_buf, win_width, win_height, self.rom_loaded && self.chip_paused);
+        self.presented_frames += 1;
+
+        self.window.update_with_buffer(&self.frame_buf, win_width, win_height).unwrap();
+    }
+
+    pub fn set_cycle_rate(&mut self, rate_hz: u32) {
+        self.chip.set_cycle_rate(rate_hz);
+    }
+
+    pub fn set_watch_rom(&mut self, watch: bool) {
+        self.rom.set_watch(watch);
+    }
+
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.chip.set_tracer(Some(tracer));
+    }
+
+    pub fn enable_coverage(&mut self) {
+        self.chip.set_coverage_enabled(true);
+    }
+
+    // Prints what the profiler counted since the last report and starts counting afresh.
+    fn print_profiler_report(&mut self) {
+        match self.chip.profiler_mut() {
+            Some(profiler) => {
+                println!("{}", profiler.report());
+                profiler.reset_counts();
+                self.notify("Profiler report printed");
+            }
+            None => self.notify("Profiler is off, enable it from the Debug menu or with --profile"),
+        }
+    }
+
+    pub fn set_profiler_enabled(&mut self, enabled: bool) {
+        self.chip.set_profiler_enabled(enabled);
+    }
+
+    pub fn set_write_warnings(&mut self, enabled: bool) {
+        self.chip.set_write_monitor_enabled(enabled);
+    }
+
+    fn report_write_warnings(&mut self) {
+        let events = self.chip.write_monitor_mut().map(WriteMonitor::take_events).unwrap_or_default();
+
+        for event in &events {
+            println!("{}", event.describe());
+        }
+
+        if let Some(event) = events.first() {
+            let message = if events.len() > 1 { format!("{} (+{} more)", event.describe(), events.len() - 1) } else { event.describe() };
+            self.notify(&message);
+        }
+    }
+
+    fn toggle_heat_map(&mut self) {
+        let show = !self.heat_map.is_visible();
+        if show {
+            self.chip.set_coverage_enabled(true);
+        }
+        self.heat_map.set_visible(show);
+    }
+
+    fn export_coverage_report(&mut self) {
+        let Some(coverage) = self.chip.coverage() else {
+            println!("Coverage is not enabled, open the heat map or run with --coverage first");
+            return;
+        };
+
+        let res = rfd::FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .set_directory(self.rom.path())
+            .save_file();
+
+        if let Some(path) = res {
+            if let Err(e) = std::fs::write(&path, coverage.report(self.chip.rom_size())) {
+                println!("Failed to write coverage report: {}", e);
+            }
+        }
+    }
+
+    fn toggle_memory_viewer(&mut self) {
+        if self.memory_viewer.take().is_some() {
+            return;
+        }
+
+        match MemoryViewer::new(&self.chip) {
+            Ok(viewer) => self.memory_viewer = Some(viewer),
+            Err(e) => println!("Failed to open memory viewer: {}", e),
+        }
+    }
+
+    fn update_memory_viewer(&mut self) {
+        if let Some(viewer) = &mut self.memory_viewer {
+            if !viewer.is_open() {
+                self.memory_viewer = None;
+                return;
+            }
+
+            viewer.update(&mut self.chip, self.chip_paused, &self.labels);
+        }
+    }
+
+    fn toggle_sprite_viewer(&mut self) {
+        if self.sprite_viewer.take().is_some() {
+            return;
+        }
+
+        match SpriteViewer::new(&self.chip) {
+            Ok(viewer) => self.sprite_viewer = Some(viewer),
+            Err(e) => println!("Failed to open sprite viewer: {}", e),
+        }
+    }
+
+    fn update_sprite_viewer(&mut self) {
+        if let Some(viewer) = &mut self.sprite_viewer {
+            if !viewer.is_open() {
+                self.sprite_viewer = None;
+                return;
+            }
+
+            viewer.update(&self.chip);
+        }
+    }
+
+    fn export_ram(&mut self) {
+        let res = rfd::FileDialog::new()
+            .add_filter("Chip8 ROM", &["ch8"])
+            .set_directory(self.rom.path())
+            .save_file();
+
+        if let Some(path) = res {
+            if let Err(e) = std::fs::write(&path, self.chip.export_rom()) {
+                println!("Failed to export RAM: {}", e);
+            }
+        }
+    }
+
+    fn current_frame(&self) -> (&[u32], usize, usize) {
+        if self.chip.high_res_mode() {
+            (&self.schip_screen_buf, ChipCore::SCHIP_SCR_WIDTH, ChipCore::SCHIP_SCR_HEIGHT)
+        }
+        else {
+            (&self.chip_screen_buf, ChipCore::CHIP_SCR_WIDTH, ChipCore::CHIP_SCR_HEIGHT)
+        }
+    }
+
+    fn next_capture_path(&self, extension: &str) -> PathBuf {
+        let rom_path = self.rom.path();
+        let dir = if rom_path.is_dir() { rom_path } else { rom_path.parent().unwrap_or(Path::new(".")) };
+        let stem = if self.rom_loaded { rom_path.file_stem().and_then(|s| s.to_str()).unwrap_or(Self::APP_NAME) } else { Self::APP_NAME };
+
+        (1..).map(|i| dir.join(format!("{}-{:03}.{}", stem, i, extension)))
+            .find(|path| !path.exists())
+            .unwrap()
+    }
+
+    fn save_screenshot(&mut self) {
+        let (pixels, width, height) = self.current_frame();
+        let png = capture::encode_png(pixels, width, height, self.capture_scale);
+        let path = self.next_capture_path("png");
+
+        match fs::write(&path, png) {
+            Ok(_) => self.notify(&format!("Saved {}", path.display())),
+            Err(e) => {
+                println!("Failed to save screenshot: {}", e);
+                self.notify("Screenshot failed");
+            }
+        }
+    }
+
+    fn toggle_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            // Record at hires size so switching resolution mid-recording keeps the same physical size.
+            self.recorder = Some(GifRecorder::new(ChipCore::SCHIP_SCR_WIDTH, ChipCore::SCHIP_SCR_HEIGHT, self.capture_scale.div_ceil(2)));
+            self.notify("Recording");
+            return;
+        };
+
+        let path = self.next_capture_path("gif");
+        match fs::write(&path, recorder.finish()) {
+            Ok(_) => self.notify(&format!("Saved {}", path.display())),
+            Err(e) => {
+                println!("Failed to save recording: {}", e);
+                self.notify("Recording failed");
+            }
+        }
+    }
+
+    fn record_frame(&mut self) {
+        if let Some(mut recorder) = self.recorder.take() {
+            let (pixels, width, height) = self.current_frame();
+            recorder.add_frame(pixels, width, height);
+            self.recorder = Some(recorder);
+        }
+    }
+
+    fn dump_trace_ring(&mut self) {
+        if let Some(tracer) = self.chip.tracer_mut() {
+            tracer.flush();
+        }
+
+        if let Some(tracer) = self.chip.tracer() {
+            for line in tracer.ring_lines() {
+                println!("{}", line);
+            }
+        }
+    }
+
     fn update_window_title(&mut self) {
-        let title = if self.chip_paused {
+        let mut title = if self.chip_paused {
             format!("{} (Paused)", Self::APP_NAME)
         }
         else {
-            format!("{} (IPF: {})", Self::APP_NAME, self.ipf)
+            format!("{} ({} Hz)", Self::APP_NAME, self.chip.cycle_rate())
         };
 
+        if self.speed_index != Self::NORMAL_SPEED_INDEX {
+            title += &format!(" [{}x]", Self::SPEED_PRESETS[self.speed_index]);
+        }
+        if let Some(platform) = self.platform {
+            title += &format!(" - {}", platform.name());
+        }
+
         self.window.set_title(title.as_str());
     }
 
+    fn is_turbo(&self) -> bool {
+        self.window.is_key_down(Key::Space)
+    }
+
+    // Speed scales the emulated time per frame, so instructions and timers stay in step.
+    fn speed(&self) -> f64 {
+        if self.is_turbo() { Self::TURBO_SPEED } else { Self::SPEED_PRESETS[self.speed_index] }
+    }
+
+    fn set_speed_index(&mut self, index: usize) {
+        self.speed_index = index.min(Self::SPEED_PRESETS.len() - 1);
+        self.notify(&format!("Speed {}x", Self::SPEED_PRESETS[self.speed_index]));
+        self.update_window_title();
+    }
+
+    pub fn set_speed(&mut self, speed: f64) -> bool {
+        match Self::SPEED_PRESETS.iter().position(|&preset| preset == speed) {
+            Some(index) => {
+                self.set_speed_index(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.chip_paused = paused;
+        self.update_window_title();
+
+        if self.chip_paused {
+            self.dump_trace_ring();
+        }
+        else {
+            self.discard_key_events();
+        }
+    }
+
+    // Drops key events nothing is running to consume, so they don't pile up and replay on resume.
+    fn discard_key_events(&mut self) {
+        self.key_events.drain();
+        self.last_input_time = Instant::now();
+    }
+
+    // The core drops its queued key events when it resets, so the keys are queued again from
+    // scratch. Macros and autofire carry on, the core's cycle counter keeps running for them.
+    fn reset_queued_input(&mut self) {
+        self.queued_keys = [false; 16];
+        self.discard_key_events();
+    }
+
+    fn notify(&mut self, message: &str) {
+        self.osd.notify(message);
+    }
+
+    pub fn set_quirks(&mut self, name: &str, quirks: Quirks) {
+        self.chip.set_quirks(quirks);
+        self.notify(&format!("{} quirks", name));
+    }
+
+    // Picks the quirks and cycle rate of the forced platform, or of the one guessed from the
+    // loaded ROM's opcodes.
+    fn apply_platform(&mut self) {
+        let detection = match self.platform_override {
+            Some(platform) => Detection { platform, evidence: None },
+            None => Platform::detect(&self.chip.ram()[0x200..0x200 + self.chip.rom_size()]),
+        };
+
+        self.chip.set_quirks(detection.platform.quirks());
+        self.chip.set_cycle_rate(detection.platform.cycle_rate());
+        self.platform = Some(detection.platform);
+        self.notify(&format!("{} {}", if self.platform_override.is_some() { "Platform" } else { "Detected" }, detection.describe()));
+        self.update_window_title();
+    }
+
+    // None goes back to detecting the platform from the ROM.
+    pub fn set_platform(&mut self, platform: Option<Platform>) {
+        self.platform_override = platform;
+
+        if self.rom_loaded {
+            self.apply_platform();
+        }
+        else {
+            self.notify(&format!("Platform {}", platform.map_or("auto", Platform::name)));
+        }
+    }
+
+    // Toggles autofire for the CHIP-8 keys currently held, or turns it off for all keys when
+    // none are held.
+    fn toggle_autofire(&mut self) {
+        let held: Vec<usize> = (0..16).filter(|&i| self.keyboard_keys[i] || self.mouse_key == Some(i as u8)).collect();
+
+        if held.is_empty() {
+            self.autofire.clear();
+            self.notify("Autofire off");
+            return;
+        }
+
+        for key in held {
+            let enabled = !self.autofire.is_enabled(key);
+            self.autofire.set_enabled(key, enabled);
+            let state = if enabled { format!("on at {} Hz", self.autofire.rate()) } else { "off".to_string() };
+            self.notify(&format!("Autofire {:X} {}", key, state));
+        }
+    }
+
+    pub fn enable_autofire(&mut self, key: u8) {
+        self.autofire.set_enabled((key & 0xF) as usize, true);
+    }
+
+    pub fn set_autofire_rate(&mut self, rate_hz: u32) {
+        self.autofire.set_rate(rate_hz);
+    }
+
+    fn toggle_macro_recording(&mut self) {
+        let cycle = self.chip.cycle_count();
+
+        match self.macro_recorder.take() {
+            Some(recorder) => {
+                self.input_macro = Some(recorder.finish(cycle));
+                self.notify("Macro recorded");
+            }
+            None => {
+                self.macro_playback = None;
+                self.macro_recorder = Some(MacroRecorder::new(cycle));
+                self.notify("Recording macro");
+            }
+        }
+    }
+
+    fn play_macro(&mut self) {
+        if self.input_macro.is_none() {
+            self.notify("No macro recorded, Shift+F10 to record one");
+            return;
+        }
+
+        self.macro_keys = [false; 16];
+        self.macro_playback = Some(MacroPlayback::new(self.chip.cycle_count()));
+        self.notify("Playing macro");
+    }
+
+    pub fn set_show_keypad(&mut self, show: bool) {
+        self.keypad.set_visible(show);
+        self.mouse_key = None;
+    }
+
+    // The keypad key under the mouse while the left button is held.
+    fn update_mouse_key(&mut self) {
+        let (width, height) = self.window.get_size();
+
+        self.mouse_key = if self.window.get_mouse_down(MouseButton::Left) {
+            self.window.get_mouse_pos(MouseMode::Discard)
+                .and_then(|(x, y)| self.keypad.key_at(width, height, x as usize, y as usize))
+        }
+        else {
+            None
+        };
+    }
+
+    pub fn set_show_stats(&mut self, show: bool) {
+        self.osd.set_show_stats(show);
+    }
+
+
     fn check_seconds_timer(&mut self) {
-        if self.seconds_timer.elapsed() >= Duration::from_secs(1) && self.rom_loaded && !self.chip_paused {
-            let avg_exec_time = (self.execute_times / self.execute_count as f64) * 1000.0;
-            println!("Average execute time: {:.3} ms", avg_exec_time);
+        let elapsed = self.seconds_timer.elapsed().as_secs_f64();
+
+        if elapsed >= 1.0 {
+            let cycles = self.chip.cycle_count().saturating_sub(self.last_cycle_count);
+            let frames = self.execute_count.max(1) as f64;
+
+            self.osd.set_stats(Stats {
+                fps: self.presented_frames as f64 / elapsed,
+                ipf: cycles as f64 / frames,
+                mips: cycles as f64 / elapsed / 1_000_000.0,
+                frame_time_ms: self.execute_times / frames * 1000.0,
+            });
+
+            let dropped = self.pacer.take_dropped_ticks();
+            if dropped > 0 && self.rom_loaded {
+                self.notify(&format!("Dropped {} frames", dropped));
+            }
+
+            self.report_write_warnings();
 
             self.execute_times = 0.0;
             self.execute_count = 0;
+            self.presented_frames = 0;
+            self.last_cycle_count = self.chip.cycle_count();
             self.seconds_timer = Instant::now();
         }
     }
 
-    fn load_rom(&mut self) {
-        if self.chip.load_rom(self.rom_path.as_path()) {
+    fn load_octo_source(&mut self, path: &Path) -> bool {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                println!("Failed to read {}: {}", path.display(), e);
+                return false;
+            }
+        };
+
+        match octo::compile(&source) {
+            Ok(program) => {
+                if self.chip.load_rom_bytes(&program.bytes) {
+                    self.labels = program.labels;
+                    return true;
+                }
+
+                println!("{}: program does not fit in memory", path.display());
+                false
+            }
+            Err(e) => {
+                println!("{}:{}", path.display(), e);
+                self.notify(&e.to_string());
+                false
+            }
+        }
+    }
+
+    fn load_rom(&mut self) -> bool {
+        self.load_rom_from(self.rom.path().to_path_buf())
+    }
+
+    fn load_rom_from(&mut self, path: PathBuf) -> bool {
+        let loaded = if rom_watcher::is_octo_source(&path) {
+            self.load_octo_source(&path)
+        }
+        else if self.chip.load_rom(&path) {
+            self.labels.clear();
+            true
+        }
+        else {
+            false
+        };
+
+        self.rom.finish_load(path, loaded);
+
+        if loaded {
+            self.reset_queued_input();
             self.rom_loaded = true;
             self.chip_paused = false;
             self.update_window_title();
         }
+        loaded
+    }
+
+    pub fn open_rom(&mut self, path: PathBuf) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+        if !self.load_rom_from(path) {
+            self.notify(&format!("Failed to load {}", name));
+            return false;
+        }
+
+        self.notify(&format!("Loaded {}", name));
+        self.apply_platform();
+        true
+    }
+
+    fn soft_reset(&mut self) {
+        if self.rom_loaded {
+            self.chip.soft_reset();
+            self.reset_queued_input();
+            self.notify("Soft reset");
+        }
+    }
+
+    // Restarts from the ROM image in memory, so it works even if the file has since moved.
+    fn hard_reset(&mut self) {
+        if self.rom_loaded {
+            self.chip.hard_reset();
+            self.reset_queued_input();
+            self.notify("Hard reset");
+        }
+    }
+
+    fn check_rom_changed(&mut self) {
+        if !self.rom.poll() {
+            return;
+        }
+
+        if self.load_rom() {
+            self.notify("Reloaded");
+
+            if !self.keep_settings_on_reload {
+                self.apply_platform();
+            }
+        }
+        else {
+            self.notify("Reload failed");
+        }
+    }
+
+    fn toggle_watch_rom(&mut self) {
+        self.set_watch_rom(!self.rom.is_watching());
+        self.notify(if self.rom.is_watching() { "Watching ROM" } else { "Stopped watching ROM" });
     }
 
     fn file_load_dialog(&mut self) {
         let res = rfd::FileDialog::new()
             .add_filter("Chip8 ROM", &["ch8", "bnc"])
-            .set_directory(&self.rom_path)
+            .add_filter("Octo source", &["8o"])
+            .set_directory(self.rom.path())
             .pick_files();
 
         if let Some(paths) = res {
-            if self.chip.load_rom(paths[0].as_path()) {
-                self.rom_path = paths[0].clone();
-                self.load_rom();
-            }
+            self.open_rom(paths[0].clone());
         }
+        self.pacer.resync();
     }
 
     fn update_window(&mut self) {
@@ -139,16 +973,115 @@ impl App {
                         self.load_rom();
                     }
                 }
+                Self::FILE_MENU_SOFT_RESET_ID => {
+                    self.soft_reset();
+                }
+                Self::FILE_MENU_HARD_RESET_ID => {
+                    self.hard_reset();
+                }
+                Self::FILE_MENU_SCREENSHOT_ID => {
+                    self.save_screenshot();
+                }
+                Self::FILE_MENU_RECORD_ID => {
+                    self.toggle_recording();
+                }
+                id if (Self::CAPTURE_SCALE_MENU_BASE_ID..Self::CAPTURE_SCALE_MENU_BASE_ID + Self::CAPTURE_SCALES.len()).contains(&id) => {
+                    self.capture_scale = Self::CAPTURE_SCALES[id - Self::CAPTURE_SCALE_MENU_BASE_ID];
+                    self.notify(&format!("Capture scale {}x", self.capture_scale));
+                }
+                Self::OPTIONS_MENU_FULLSCREEN_ID => {
+                    self.toggle_fullscreen();
+                }
+                Self::OPTIONS_MENU_KEYPAD_ID => {
+                    self.set_show_keypad(!self.keypad.is_visible());
+                }
+                Self::OPTIONS_MENU_STATS_ID => {
+                    self.set_show_stats(!self.osd.show_stats());
+                }
+                Self::SCALE_MENU_AUTO_ID => {
+                    self.set_display_scale(None);
+                }
+                id if (Self::SCALE_MENU_BASE_ID..Self::SCALE_MENU_BASE_ID + Self::DISPLAY_SCALES.len()).contains(&id) => {
+                    self.set_display_scale(Some(Self::DISPLAY_SCALES[id - Self::SCALE_MENU_BASE_ID]));
+                }
+                id if (Self::FILTER_MENU_BASE_ID..Self::FILTER_MENU_BASE_ID + Filter::ALL.len()).contains(&id) => {
+                    self.toggle_filter(Filter::ALL[id - Self::FILTER_MENU_BASE_ID]);
+                }
+                id if (Self::SPEED_MENU_BASE_ID..Self::SPEED_MENU_BASE_ID + Self::SPEED_PRESETS.len()).contains(&id) => {
+                    self.set_speed_index(id - Self::SPEED_MENU_BASE_ID);
+                }
+                id if (Self::QUIRKS_MENU_BASE_ID..Self::QUIRKS_MENU_BASE_ID + Quirks::PRESETS.len()).contains(&id) => {
+                    let (name, quirks) = Quirks::PRESETS[id - Self::QUIRKS_MENU_BASE_ID];
+                    self.set_quirks(name, quirks);
+                }
+                Self::PLATFORM_MENU_AUTO_ID => {
+                    self.set_platform(None);
+                }
+                id if (Self::PLATFORM_MENU_BASE_ID..Self::PLATFORM_MENU_BASE_ID + Platform::ALL.len()).contains(&id) => {
+                    self.set_platform(Some(Platform::ALL[id - Self::PLATFORM_MENU_BASE_ID]));
+                }
+                Self::OPTIONS_MENU_WATCH_ID => {
+                    self.toggle_watch_rom();
+                }
+                Self::OPTIONS_MENU_KEEP_SETTINGS_ID => {
+                    self.keep_settings_on_reload = !self.keep_settings_on_reload;
+                    self.notify(if self.keep_settings_on_reload { "Keeping settings on reload" } else { "Resetting settings on reload" });
+                }
+                Self::DEBUG_MENU_HEAT_MAP_ID => {
+                    self.toggle_heat_map();
+                }
+                Self::DEBUG_MENU_EXPORT_COVERAGE_ID => {
+                    self.export_coverage_report();
+                }
+                Self::DEBUG_MENU_MEMORY_VIEWER_ID => {
+                    self.toggle_memory_viewer();
+                }
+                Self::DEBUG_MENU_EXPORT_RAM_ID => {
+                    self.export_ram();
+                }
+                Self::DEBUG_MENU_SPRITE_VIEWER_ID => {
+                    self.toggle_sprite_viewer();
+                }
+                Self::DEBUG_MENU_PROFILER_ID => {
+                    let enabled = self.chip.profiler_mut().is_none();
+                    if !enabled {
+                        self.print_profiler_report();
+                    }
+                    self.set_profiler_enabled(enabled);
+                }
+                Self::DEBUG_MENU_PROFILER_REPORT_ID => {
+                    self.print_profiler_report();
+                }
+                Self::DEBUG_MENU_WRITE_WARNINGS_ID => {
+                    let enabled = self.chip.write_monitor_mut().is_none();
+                    self.set_write_warnings(enabled);
+                    self.notify(if enabled { "Write warnings on" } else { "Write warnings off" });
+                }
                 _ => {}
             }
         }
 
+        self.present();
+    }
+
+    // One frame: input, `frame_duration` of emulated time and the framebuffer copy.
+    fn run_frame(&mut self, frame_duration: Duration) {
+        self.update_chip_input(frame_duration);
+
+        let execute_start = Instant::now();
+        self.chip.run_for(frame_duration);
+
+        self.execute_times += execute_start.elapsed().as_secs_f64();
+        self.execute_count += 1;
+
         if self.chip.high_res_mode() {
-            self.window.update_with_buffer(&self.schip_screen_buf, ChipCore::SCHIP_SCR_WIDTH, ChipCore::SCHIP_SCR_HEIGHT).unwrap();
+            self.chip.render_to_rgb_schip_buffer(&mut self.schip_screen_buf);
         }
         else {
-            self.window.update_with_buffer(&self.chip_screen_buf, ChipCore::CHIP_SCR_WIDTH, ChipCore::CHIP_SCR_HEIGHT).unwrap();
+            self.chip.render_to_rgb_chip_buffer(&mut self.chip_screen_buf);
         }
+
+        self.record_frame();
     }
 
     pub fn run(&mut self) {
@@ -157,36 +1090,113 @@ impl App {
                 self.file_load_dialog();
             }
 
-            if self.rom_loaded {
-                if self.window.is_key_pressed(Key::Tab, KeyRepeat::No) {
-                    self.chip_paused = !self.chip_paused;
-                    self.update_window_title();
+            if self.window.is_key_pressed(Key::F11, KeyRepeat::No) {
+                self.toggle_fullscreen();
+            }
+
+            if self.window.is_key_pressed(Key::F6, KeyRepeat::No) {
+                if self.window.is_key_down(Key::LeftShift) || self.window.is_key_down(Key::RightShift) {
+                    self.print_profiler_report();
+                }
+                else {
+                    self.set_show_stats(!self.osd.show_stats());
                 }
+            }
 
-                if !self.chip_paused {
-                    self.update_chip_input();
-                    self.chip.update_timers();
+            if self.window.is_key_pressed(Key::F5, KeyRepeat::No) {
+                if self.window.is_key_down(Key::LeftShift) || self.window.is_key_down(Key::RightShift) {
+                    self.hard_reset();
+                }
+                else {
+                    self.soft_reset();
+                }
+            }
 
-                    let execute_start = Instant::now();
+            if self.window.is_key_pressed(Key::F9, KeyRepeat::No) {
+                self.toggle_autofire();
+            }
 
-                    for _ in 0..self.ipf {
-                        self.chip.execute();
-                    }
+            if self.window.is_key_pressed(Key::F10, KeyRepeat::No) {
+                if self.window.is_key_down(Key::LeftShift) || self.window.is_key_down(Key::RightShift) {
+                    self.toggle_macro_recording();
+                }
+                else {
+                    self.play_macro();
+                }
+            }
 
-                    self.execute_times += execute_start.elapsed().as_secs_f64();
-                    self.execute_count += 1;
+            if self.window.is_key_pressed(Key::F7, KeyRepeat::No) {
+                self.set_show_keypad(!self.keypad.is_visible());
+            }
 
-                    if self.chip.high_res_mode() {
-                        self.chip.render_to_rgb_schip_buffer(&mut self.schip_screen_buf);
+            if self.window.is_key_pressed(Key::F8, KeyRepeat::No) {
+                self.toggle_heat_map();
+            }
+            self.update_mouse_key();
+
+            for (key, filter) in Self::FILTER_KEYS.into_iter().zip(Filter::ALL) {
+                if self.window.is_key_pressed(key, KeyRepeat::No) {
+                    self.toggle_filter(filter);
+                }
+            }
+
+            if self.window.is_key_pressed(Key::F12, KeyRepeat::No) {
+                if self.window.is_key_down(Key::LeftShift) || self.window.is_key_down(Key::RightShift) {
+                    self.toggle_recording();
+                }
+                else {
+                    self.save_screenshot();
+                }
+            }
+
+            let ticks = self.pacer.due_ticks();
+            let frame_duration = Duration::from_secs(1) / Self::TICK_RATE;
+
+            if self.rom_loaded {
+                if self.window.is_key_pressed(Key::P, KeyRepeat::No) {
+                    self.set_paused(!self.chip_paused);
+                }
+
+                // Tab pauses, and advances a single 1/60 s frame when already paused, whatever the speed.
+                if self.window.is_key_pressed(Key::Tab, KeyRepeat::Yes) {
+                    if self.chip_paused {
+                        self.run_frame(frame_duration);
+                        self.dump_trace_ring();
                     }
                     else {
-                        self.chip.render_to_rgb_chip_buffer(&mut self.chip_screen_buf);
+                        self.set_paused(true);
                     }
                 }
+
+                if self.window.is_key_pressed(Key::Minus, KeyRepeat::No) && self.speed_index > 0 {
+                    self.set_speed_index(self.speed_index - 1);
+                }
+                else if self.window.is_key_pressed(Key::Equal, KeyRepeat::No) {
+                    self.set_speed_index(self.speed_index + 1);
+                }
+
+                if !self.chip_paused {
+                    for _ in 0..ticks {
+                        self.run_frame(frame_duration.mul_f64(self.speed()));
+                    }
+                }
+            }
+
+            if !self.rom_loaded || self.chip_paused {
+                self.discard_key_events();
             }
 
             self.check_seconds_timer();
+            self.check_rom_changed();
+            self.osd.update();
+            self.update_memory_viewer();
+            self.update_sprite_viewer();
             self.update_window();
+            self.pacer.wait_for_next_tick();
+        }
+
+        if let Some(profiler) = self.chip.profiler_mut() {
+            println!("{}", profiler.report());
         }
     }
 }
\ No newline at end of file
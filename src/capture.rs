@@ -0,0 +1,269 @@
+// Dependency-free PNG and GIF encoders for screenshots and recordings of the
+// monochrome framebuffer. Pixels are thresholded to black and white.
+use std::collections::HashMap;
+
+fn is_lit(pixel: u32) -> bool {
+    let r = (pixel >> 16) & 0xFF;
+    let g = (pixel >> 8) & 0xFF;
+    let b = pixel & 0xFF;
+    r + g + b >= 0x180
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// zlib stream made of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut chunks = data.chunks(0xFFFF).peekable();
+
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+
+    while let Some(chunk) = chunks.next() {
+        let len = chunk.len() as u16;
+        out.push(chunks.peek().is_none() as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// 1-bit grayscale PNG, each source pixel scaled to a `scale` x `scale` block.
+pub fn encode_png(pixels: &[u32], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    let out_width = width * scale;
+    let out_height = height * scale;
+    let row_bytes = out_width.div_ceil(8);
+
+    let mut raw = Vec::with_capacity((row_bytes + 1) * out_height);
+    for y in 0..out_height {
+        raw.push(0); // Filter type: none.
+
+        let mut row = vec![0u8; row_bytes];
+        for x in 0..out_width {
+            if is_lit(pixels[(y / scale) * width + x / scale]) {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        raw.extend_from_slice(&row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(out_width as u32).to_be_bytes());
+    header.extend_from_slice(&(out_height as u32).to_be_bytes());
+    header.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    png_chunk(&mut out, b"IHDR", &header);
+    png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear_code = 1u16 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+    let mut writer = BitWriter { bytes: Vec::new(), acc: 0, bits: 0 };
+
+    writer.write(clear_code, code_size);
+
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+
+        if let Some(&code) = table.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        writer.write(current, code_size);
+
+        if next_code == 4096 {
+            writer.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        }
+        else {
+            table.insert((current, index), next_code);
+            if next_code == (1 << code_size) && code_size < 12 {
+                code_size += 1;
+            }
+            next_code += 1;
+        }
+
+        prefix = Some(index as u16);
+    }
+
+    // The decoder adds a table entry for the last code too, so the end code needs the wider
+    // size if that entry fills the current one.
+    if let Some(current) = prefix {
+        writer.write(current, code_size);
+        if next_code == (1 << code_size) && code_size < 12 {
+            code_size += 1;
+        }
+    }
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+pub struct GifRecorder {
+    width: usize,
+    height: usize,
+    scale: usize,
+    data: Vec<u8>,
+    pending: Option<(Vec<u8>, f64)>,
+    written_time: f64,
+    elapsed_time: f64,
+}
+
+impl GifRecorder {
+    const FRAME_TIME_CS: f64 = 100.0 / 60.0;
+
+    pub fn new(width: usize, height: usize, scale: usize) -> Self {
+        let out_width = (width * scale) as u16;
+        let out_height = (height * scale) as u16;
+
+        let mut data = b"GIF89a".to_vec();
+        data.extend_from_slice(&out_width.to_le_bytes());
+        data.extend_from_slice(&out_height.to_le_bytes());
+        data.extend_from_slice(&[0x80, 0, 0]); // Global color table with 2 entries.
+        data.extend_from_slice(&[0, 0, 0, 0xFF, 0xFF, 0xFF]);
+        // Loop forever.
+        data.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        data.extend_from_slice(b"NETSCAPE2.0");
+        data.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        Self { width, height, scale, data, pending: None, written_time: 0.0, elapsed_time: 0.0 }
+    }
+
+    // Adds one 60 Hz frame. Frames of a different size are nearest-neighbour scaled to the
+    // recording size, so switching between lores and hires mid-recording keeps working.
+    pub fn add_frame(&mut self, pixels: &[u32], width: usize, height: usize) {
+        let out_width = self.width * self.scale;
+        let out_height = self.height * self.scale;
+
+        let indices: Vec<u8> = (0..out_width * out_height)
+            .map(|i| {
+                let x = (i % out_width) * width / out_width;
+                let y = (i / out_width) * height / out_height;
+                is_lit(pixels[y * width + x]) as u8
+            })
+            .collect();
+
+        match &mut self.pending {
+            Some((pending, duration)) if *pending == indices => *duration += Self::FRAME_TIME_CS,
+            _ => {
+                self.flush_pending();
+                self.pending = Some((indices, Self::FRAME_TIME_CS));
+            }
+        }
+    }
+
+    // GIF delays are whole centiseconds, so the rounding error is carried into the next
+    // frame to keep the total duration in sync with 60 Hz.
+    fn flush_pending(&mut self) {
+        let Some((indices, duration)) = self.pending.take() else {
+            return;
+        };
+
+        self.elapsed_time += duration;
+        let delay = (self.elapsed_time - self.written_time).round() as u16;
+        self.written_time += delay as f64;
+
+        let out_width = (self.width * self.scale) as u16;
+        let out_height = (self.height * self.scale) as u16;
+
+        self.data.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+        self.data.extend_from_slice(&delay.to_le_bytes());
+        self.data.extend_from_slice(&[0x00, 0x00]);
+
+        self.data.push(0x2C);
+        self.data.extend_from_slice(&[0, 0, 0, 0]);
+        self.data.extend_from_slice(&out_width.to_le_bytes());
+        self.data.extend_from_slice(&out_height.to_le_bytes());
+        self.data.push(0);
+
+        self.data.push(2);
+        for block in lzw_encode(&indices, 2).chunks(255) {
+            self.data.push(block.len() as u8);
+            self.data.extend_from_slice(block);
+        }
+        self.data.push(0);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.flush_pending();
+        self.data.push(0x3B);
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests;
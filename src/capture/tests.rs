@@ -0,0 +1,251 @@
+use super::*;
+
+const W: u32 = 0xFFFFFF;
+const B: u32 = 0x000000;
+
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// Splits a PNG into its chunks, checking the signature and every CRC on the way.
+fn png_chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+
+    let mut chunks = Vec::new();
+    let mut pos = 8;
+    while pos < png.len() {
+        let len = read_u32_be(&png[pos..]) as usize;
+        let body = &png[pos + 4..pos + 8 + len];
+        assert_eq!(read_u32_be(&png[pos + 8 + len..]), crc32(body));
+
+        chunks.push(([body[0], body[1], body[2], body[3]], body[4..].to_vec()));
+        pos += 12 + len;
+    }
+    chunks
+}
+
+// Just enough inflate for zlib streams made of stored blocks.
+fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+    assert_eq!(((zlib[0] as u16) << 8 | zlib[1] as u16) % 31, 0);
+
+    let mut out = Vec::new();
+    let mut pos = 2;
+    loop {
+        let header = zlib[pos];
+        assert_eq!(header & 0x06, 0, "only stored blocks are expected");
+
+        let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]);
+        let nlen = u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]);
+        assert_eq!(len, !nlen);
+
+        out.extend_from_slice(&zlib[pos + 5..pos + 5 + len as usize]);
+        pos += 5 + len as usize;
+        if header & 1 == 1 {
+            break;
+        }
+    }
+
+    assert_eq!(read_u32_be(&zlib[pos..]), adler32(&out));
+    assert_eq!(pos + 4, zlib.len());
+    out
+}
+
+// A plain GIF LZW decoder, strict about code sizes: reading past the data panics and the end
+// code must finish in the last byte.
+fn lzw_decode(data: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear_code = 1usize << min_code_size;
+    let end_code = clear_code + 1;
+    let initial_table = || -> Vec<Vec<u8>> { (0..=end_code).map(|i| vec![i as u8]).collect() };
+
+    let mut table = initial_table();
+    let mut code_size = min_code_size + 1;
+    let mut prev: Option<Vec<u8>> = None;
+    let mut out = Vec::new();
+    let mut bit = 0;
+
+    loop {
+        let mut code = 0;
+        for i in 0..code_size as usize {
+            let byte = data.get((bit + i) / 8).expect("ran out of data before the end code");
+            code |= ((*byte as usize >> ((bit + i) % 8)) & 1) << i;
+        }
+        bit += code_size as usize;
+
+        if code == clear_code {
+            table = initial_table();
+            code_size = min_code_size + 1;
+            prev = None;
+            continue;
+        }
+        if code == end_code {
+            break;
+        }
+
+        let entry = match &prev {
+            _ if code < table.len() => table[code].clone(),
+            Some(prev) if code == table.len() => [prev.as_slice(), &prev[..1]].concat(),
+            _ => panic!("code {} is not in the table yet", code),
+        };
+
+        if let Some(prev) = prev.filter(|_| table.len() < 4096) {
+            table.push([prev.as_slice(), &entry[..1]].concat());
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        }
+
+        out.extend_from_slice(&entry);
+        prev = Some(entry);
+    }
+
+    assert_eq!(bit.div_ceil(8), data.len(), "data after the end code");
+    out
+}
+
+struct GifFrame {
+    delay: u16,
+    width: u16,
+    height: u16,
+    indices: Vec<u8>,
+}
+
+fn gif_frames(gif: &[u8]) -> (u16, u16, Vec<GifFrame>) {
+    assert_eq!(&gif[..6], b"GIF89a");
+    let width = u16::from_le_bytes([gif[6], gif[7]]);
+    let height = u16::from_le_bytes([gif[8], gif[9]]);
+    assert_eq!(gif[10], 0x80);
+
+    let sub_blocks = |pos: &mut usize| -> Vec<u8> {
+        let mut data = Vec::new();
+        while gif[*pos] != 0 {
+            let len = gif[*pos] as usize;
+            data.extend_from_slice(&gif[*pos + 1..*pos + 1 + len]);
+            *pos += 1 + len;
+        }
+        *pos += 1;
+        data
+    };
+
+    let mut frames = Vec::new();
+    let mut delay = 0;
+    let mut pos = 13 + 6;
+    loop {
+        match gif[pos] {
+            0x21 => {
+                let label = gif[pos + 1];
+                pos += 2;
+                let data = sub_blocks(&mut pos);
+                if label == 0xF9 {
+                    delay = u16::from_le_bytes([data[1], data[2]]);
+                }
+            }
+            0x2C => {
+                let frame_width = u16::from_le_bytes([gif[pos + 5], gif[pos + 6]]);
+                let frame_height = u16::from_le_bytes([gif[pos + 7], gif[pos + 8]]);
+                let min_code_size = gif[pos + 10] as u32;
+                pos += 11;
+                let indices = lzw_decode(&sub_blocks(&mut pos), min_code_size);
+                frames.push(GifFrame { delay, width: frame_width, height: frame_height, indices });
+            }
+            0x3B => {
+                assert_eq!(pos + 1, gif.len());
+                return (width, height, frames);
+            }
+            other => panic!("unexpected block {:02X} at {}", other, pos),
+        }
+    }
+}
+
+#[test]
+fn checksums_match_known_values() {
+    assert_eq!(crc32(b"IEND"), 0xAE426082);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+}
+
+#[test]
+fn png_round_trips_a_2x2_frame() {
+    let chunks = png_chunks(&encode_png(&[W, B, B, W], 2, 2, 1));
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+    assert_eq!(chunks[0].1, [0, 0, 0, 2, 0, 0, 0, 2, 1, 0, 0, 0, 0]);
+    // One filter byte and one packed byte per row, most significant bit first.
+    assert_eq!(inflate_stored(&chunks[1].1), [0, 0x80, 0, 0x40]);
+}
+
+#[test]
+fn png_scales_pixels_into_blocks() {
+    let chunks = png_chunks(&encode_png(&[B, W], 2, 1, 3));
+    assert_eq!(read_u32_be(&chunks[0].1), 6);
+    assert_eq!(read_u32_be(&chunks[0].1[4..]), 3);
+    assert_eq!(inflate_stored(&chunks[1].1), [0, 0x1C, 0, 0x1C, 0, 0x1C]);
+}
+
+#[test]
+fn png_splits_large_images_into_stored_blocks() {
+    let pixels = vec![W; 1024 * 1024];
+    let chunks = png_chunks(&encode_png(&pixels, 1024, 1024, 1));
+    let raw = inflate_stored(&chunks[1].1);
+
+    assert_eq!(raw.len(), 1024 * 129);
+    assert!(raw.chunks(129).all(|row| row[0] == 0 && row[1..].iter().all(|&byte| byte == 0xFF)));
+}
+
+#[test]
+fn gif_round_trips_a_2x2_frame() {
+    let mut recorder = GifRecorder::new(2, 2, 1);
+    recorder.add_frame(&[W, B, B, W], 2, 2);
+
+    let (width, height, frames) = gif_frames(&recorder.finish());
+    assert_eq!((width, height), (2, 2));
+    assert_eq!(frames.len(), 1);
+    assert_eq!((frames[0].width, frames[0].height), (2, 2));
+    assert_eq!(frames[0].indices, [1, 0, 0, 1]);
+}
+
+#[test]
+fn gif_merges_repeated_frames_and_scales_other_sizes() {
+    let mut recorder = GifRecorder::new(2, 1, 2);
+    for _ in 0..3 {
+        recorder.add_frame(&[W, B], 2, 1);
+    }
+    recorder.add_frame(&[B, B, W, W], 4, 1);
+
+    let (width, height, frames) = gif_frames(&recorder.finish());
+    assert_eq!((width, height), (4, 2));
+    assert_eq!(frames.iter().map(|frame| frame.delay).collect::<Vec<_>>(), [5, 2]);
+    assert_eq!(frames[0].indices, [1, 1, 0, 0, 1, 1, 0, 0]);
+    assert_eq!(frames[1].indices, [0, 0, 1, 1, 0, 0, 1, 1]);
+}
+
+#[test]
+fn lzw_round_trips_across_code_size_changes() {
+    // Every length up to a few thousand pixels crosses the first few code size changes,
+    // including inputs whose last code fills the table at the current size.
+    let mut state = 0x12345678u32;
+    let noise: Vec<u8> = (0..3000)
+        .map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as u8 & 1
+        })
+        .collect();
+
+    for len in 0..noise.len() {
+        assert_eq!(lzw_decode(&lzw_encode(&noise[..len], 2), 2), &noise[..len], "length {}", len);
+    }
+}
+
+#[test]
+fn lzw_round_trips_through_a_table_reset() {
+    let mut state = 1u32;
+    let noise: Vec<u8> = (0..40000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state & 3) as u8
+        })
+        .collect();
+
+    assert_eq!(lzw_decode(&lzw_encode(&noise, 2), 2), noise);
+}
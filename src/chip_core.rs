@@ -1,7 +1,26 @@
+use std::collections::VecDeque;
 use std::path::Path;
 use std::{fs};
-use rand::Rng;
-use rand::rngs::ThreadRng;
+use std::time::Duration;
+use rand::{ Rng, SeedableRng };
+use rand::rngs::StdRng;
+use crate::disasm;
+use crate::tracer::Tracer;
+use crate::coverage::Coverage;
+use crate::profiler::Profiler;
+use crate::write_monitor::WriteMonitor;
+use crate::quirks::{ KeyWaitMode, Quirks };
+use crate::lockstep::{ CoreState };
+
+// Progress of an FX0A instruction. The key has to go down while waiting, so a key that was
+// already held when FX0A started does not count.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyWait {
+    Idle,
+    WaitingForPress { reg: u8 },
+    WaitingForRelease { reg: u8, key: u8 },
+    Complete,
+}
 
 pub struct ChipCore {
     screen_buf: [u64; ChipCore::CHIP_SCR_HEIGHT],
@@ -10,15 +29,33 @@ pub struct ChipCore {
     regs: [u8; 16],
     stack: [u16; 16],
     keys: [bool; 16],
-    awaiting_key_release: bool,
-    released_key_reg: i8,
+    key_wait: KeyWait,
+    quirks: Quirks,
     sp: u16,
     pc: u16,
     i_reg: u16,
     delay_timer: u8,
     sound_timer: u8,
     high_res_mode: bool,
-    rng: ThreadRng,
+    rng: StdRng,
+    rng_seed: Option<u64>,
+    tracer: Option<Tracer>,
+    coverage: Option<Coverage>,
+    profiler: Option<Profiler>,
+    write_monitor: Option<WriteMonitor>,
+    rom_image: Vec<u8>,
+    cycle_rate: u32,
+    timer_phase: u32,
+    cycle_remainder: u64,
+    cycle_count: u64,
+    input_queue: VecDeque<(u64, u8, bool)>,
+    key_schedule: [u64; 16],
+}
+
+impl Default for ChipCore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ChipCore {
@@ -68,6 +105,8 @@ impl ChipCore {
         0xFE, 0x66, 0x62, 0x64, 0x7C, 0x64, 0x60, 0x60, 0xF0, 0x00, // F
     ];
     const SCHIP_FONT_OFFSET: usize = 80;
+    pub const DEFAULT_CYCLE_RATE: u32 = 660;
+    const TIMER_RATE: u32 = 60;
 
     pub fn new() -> Self {
         let mut chip_core = Self {
@@ -77,15 +116,27 @@ impl ChipCore {
             regs: [0; 16],
             stack: [0; 16],
             keys: [false; 16],
-            awaiting_key_release: false,
-            released_key_reg: -1,
+            key_wait: KeyWait::Idle,
+            quirks: Quirks::default(),
             sp: 0,
             pc: 0x200,
             i_reg: 0,
             delay_timer: 0,
             sound_timer: 0,
             high_res_mode: false,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
+            rng_seed: None,
+            tracer: None,
+            coverage: None,
+            profiler: None,
+            write_monitor: None,
+            rom_image: Vec::new(),
+            cycle_rate: Self::DEFAULT_CYCLE_RATE,
+            timer_phase: 0,
+            cycle_remainder: 0,
+            cycle_count: 0,
+            input_queue: VecDeque::new(),
+            key_schedule: [0; 16],
         };
 
         chip_core.ram[..Self::FONT_DATA.len()].copy_from_slice(&Self::FONT_DATA);
@@ -105,13 +156,69 @@ impl ChipCore {
                 Err(_) => return false,
             };
 
-            *self = Self::new();
-            self.ram[0x200..0x200 + bytes.len()].copy_from_slice(&bytes);
-            return true;
+            return self.load_rom_bytes(&bytes);
         }
         false
     }
 
+    pub fn load_rom_bytes(&mut self, bytes: &[u8]) -> bool {
+        if bytes.len() > Self::RAM_SIZE - 0x200 {
+            return false;
+        }
+
+        let tracer = self.tracer.take();
+        let coverage_enabled = self.coverage.is_some();
+        let profiler_enabled = self.profiler.is_some();
+        let write_monitor_enabled = self.write_monitor.is_some();
+        let cycle_rate = self.cycle_rate;
+        let quirks = self.quirks;
+        let rng_seed = self.rng_seed;
+        let cycle_count = self.cycle_count;
+        *self = Self::new();
+        self.cycle_rate = cycle_rate;
+        self.cycle_count = cycle_count;
+        self.quirks = quirks;
+        if let Some(seed) = rng_seed {
+            self.set_rng_seed(seed);
+        }
+        self.tracer = tracer;
+        self.set_coverage_enabled(coverage_enabled);
+        self.set_profiler_enabled(profiler_enabled);
+        self.set_write_monitor_enabled(write_monitor_enabled);
+        self.ram[0x200..0x200 + bytes.len()].copy_from_slice(bytes);
+        self.rom_image = bytes.to_vec();
+        true
+    }
+
+    // Like the reset button on a real machine: the CPU, timers and display start over but RAM,
+    // including anything the program wrote into itself, is kept.
+    pub fn soft_reset(&mut self) {
+        self.screen_buf = [0; Self::CHIP_SCR_HEIGHT];
+        self.schip_screen_buf = [0; Self::SCHIP_SCR_HEIGHT];
+        self.regs = [0; 16];
+        self.stack = [0; 16];
+        self.sp = 0;
+        self.pc = 0x200;
+        self.i_reg = 0;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.high_res_mode = false;
+        self.key_wait = KeyWait::Idle;
+        self.keys = [false; 16];
+        self.input_queue.clear();
+        self.key_schedule = [0; 16];
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.reset_call_stack();
+        }
+    }
+
+    // Reloads the ROM image that was last loaded, without going back to disk.
+    pub fn hard_reset(&mut self) {
+        let rom_image = std::mem::take(&mut self.rom_image);
+        self.load_rom_bytes(&rom_image);
+    }
+
     pub fn render_to_rgb_chip_buffer(&mut self, buf: &mut [u32]) {
         for i in 0..Self::CHIP_FRAMEBUFFER_SIZE {
             buf[i] = if ((self.screen_buf[i >> 6] >> (Self::CHIP_SCR_WIDTH - 1 - (i & 0x3F))) & 0x1) == 1 { 0xFFFFFFFF } else { 0 };
@@ -124,17 +231,138 @@ impl ChipCore {
     }
 
     pub fn key_event(&mut self, key: u8, action: bool) {
-        self.keys[(key & 0xF) as usize] = action;
+        let key = key & 0xF;
+        let was_down = std::mem::replace(&mut self.keys[key as usize], action);
 
-        if self.awaiting_key_release && !action  {
-            self.regs[self.released_key_reg as usize] = key;
-            self.released_key_reg = -1;
-        }
+        self.key_wait = match self.key_wait {
+            KeyWait::WaitingForPress { reg } if action && !was_down => {
+                match self.quirks.key_wait {
+                    KeyWaitMode::Press => {
+                        self.regs[reg as usize] = key;
+                        KeyWait::Complete
+                    }
+                    KeyWaitMode::Release => KeyWait::WaitingForRelease { reg, key },
+                }
+            }
+            KeyWait::WaitingForRelease { reg, key: held } if !action && key == held => {
+                self.regs[reg as usize] = key;
+                KeyWait::Complete
+            }
+            state => state,
+        };
+    }
+
+    pub fn key_wait(&self) -> KeyWait {
+        self.key_wait
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    // Makes CXNN deterministic, e.g. to run two cores side by side. The seed is kept across
+    // ROM loads and resets, which restart the sequence from the beginning.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.rng_seed = Some(seed);
+    }
+
+    // True while the buzzer sounds: the sound timer is running, or with the VIP buzzer quirk,
+    // a key is held down to complete FX0A.
+    pub fn is_sound_active(&self) -> bool {
+        self.sound_timer > 0 || (self.quirks.vip_buzzer && matches!(self.key_wait, KeyWait::WaitingForRelease { .. }))
     }
     pub fn get_keys(&self) -> &[bool; 16] {
         &self.keys
     }
 
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_ref()
+    }
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    pub fn set_coverage_enabled(&mut self, enabled: bool) {
+        if enabled != self.coverage.is_some() {
+            self.coverage = if enabled { Some(Coverage::new()) } else { None };
+        }
+    }
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    pub fn set_profiler_enabled(&mut self, enabled: bool) {
+        if enabled != self.profiler.is_some() {
+            self.profiler = if enabled { Some(Profiler::new()) } else { None };
+        }
+    }
+    pub fn profiler_mut(&mut self) -> Option<&mut Profiler> {
+        self.profiler.as_mut()
+    }
+
+    pub fn set_write_monitor_enabled(&mut self, enabled: bool) {
+        if enabled != self.write_monitor.is_some() {
+            self.write_monitor = if enabled { Some(WriteMonitor::new()) } else { None };
+        }
+    }
+    pub fn write_monitor_mut(&mut self) -> Option<&mut WriteMonitor> {
+        self.write_monitor.as_mut()
+    }
+
+    pub fn rom_size(&self) -> usize {
+        self.rom_image.len()
+    }
+
+    pub fn ram(&self) -> &[u8; Self::RAM_SIZE] {
+        &self.ram
+    }
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        self.ram[addr as usize & 0xFFF] = value;
+    }
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+    pub fn i_reg(&self) -> u16 {
+        self.i_reg
+    }
+
+    pub fn state(&self) -> CoreState {
+        CoreState {
+            pc: self.pc,
+            i_reg: self.i_reg,
+            sp: self.sp,
+            regs: self.regs,
+            stack: self.stack,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            keys: self.keys,
+            key_wait: self.key_wait,
+            high_res_mode: self.high_res_mode,
+            ram: self.ram.to_vec(),
+            screen: self.screen_buf.to_vec(),
+            schip_screen: self.schip_screen_buf.to_vec(),
+        }
+    }
+
+    pub fn export_rom(&self) -> Vec<u8> {
+        let last_used = self.ram[0x200..].iter().rposition(|&byte| byte != 0).map_or(0, |pos| pos + 1);
+        self.ram[0x200..0x200 + last_used.max(self.rom_image.len())].to_vec()
+    }
+
+    fn trace_line(&self, opcode: u16) -> String {
+        let mut line = format!("PC:{:04X} OP:{:04X}", self.pc, opcode);
+
+        for (i, reg) in self.regs.iter().enumerate() {
+            line += &format!(" V{:X}:{:02X}", i, reg);
+        }
+
+        line + &format!(" I:{:04X} SP:{:02X} DT:{:02X} ST:{:02X} ; {}", self.i_reg, self.sp, self.delay_timer, self.sound_timer, disasm::disassemble(opcode))
+    }
+
     pub fn high_res_mode(&self) -> bool {
         self.high_res_mode
     }
@@ -148,6 +376,67 @@ impl ChipCore {
         }
     }
     
+    pub fn cycle_rate(&self) -> u32 {
+        self.cycle_rate
+    }
+
+    // Keeps counting across ROM loads and resets, so input scheduled by cycle stays in order.
+    pub fn cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    pub fn set_cycle_rate(&mut self, rate_hz: u32) {
+        self.cycle_rate = rate_hz.max(1);
+        self.timer_phase = 0;
+        self.cycle_remainder = 0;
+    }
+
+    // Queues a key change to be applied right before the instruction at absolute cycle index
+    // `cycle`. Events for one key are kept in order, and a release is held back until the key
+    // has been down for at least one 60 Hz frame so that short taps are never missed.
+    pub fn queue_key_event(&mut self, cycle: u64, key: u8, pressed: bool) {
+        let key = key & 0xF;
+        let previous = self.key_schedule[key as usize];
+        let min_cycle = if pressed { previous } else { previous + (self.cycle_rate / Self::TIMER_RATE) as u64 };
+        let cycle = cycle.max(min_cycle).max(self.cycle_count);
+
+        self.key_schedule[key as usize] = cycle;
+
+        let pos = self.input_queue.partition_point(|&(queued, _, _)| queued <= cycle);
+        self.input_queue.insert(pos, (cycle, key, pressed));
+    }
+
+    // Executes `cycles` instructions, decrementing the 60 Hz timers at the instruction
+    // boundaries where they fall at the current cycle rate.
+    pub fn run_cycles(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            while let Some(&(cycle, key, pressed)) = self.input_queue.front() {
+                if cycle > self.cycle_count {
+                    break;
+                }
+                self.input_queue.pop_front();
+                self.key_event(key, pressed);
+            }
+
+            self.execute();
+            self.cycle_count += 1;
+
+            self.timer_phase += Self::TIMER_RATE;
+            while self.timer_phase >= self.cycle_rate {
+                self.timer_phase -= self.cycle_rate;
+                self.update_timers();
+            }
+        }
+    }
+
+    // Runs as many cycles as fit in `duration`. Fractions of a cycle are carried over to the
+    // next call, so calling this every frame adds up to exactly `cycle_rate` per second.
+    pub fn run_for(&mut self, duration: Duration) {
+        let total = duration.as_nanos() * self.cycle_rate as u128 + self.cycle_remainder as u128;
+        self.cycle_remainder = (total % 1_000_000_000) as u64;
+        self.run_cycles((total / 1_000_000_000).try_into().unwrap_or(u64::MAX));
+    }
+
     fn shift_screenbuf_down<T, const N: usize>(buf: &mut [T; N], num_pixels: usize) where T: Copy + Default  {
         for i in (num_pixels..N).rev() {
             buf[i] = buf[i - num_pixels];
@@ -157,9 +446,55 @@ impl ChipCore {
             *elem = T::default();
         }
     }
+
+    // PC always stays inside the 4 KB address space and wraps from 0xFFF back to 0x000. I is a
+    // full 16-bit register that wraps on overflow; every memory access through it is taken
+    // modulo 4 KB, so sprite, BCD and load/store accesses past 0xFFF wrap to the start of RAM.
+    fn wrap_addr(addr: u16) -> u16 {
+        addr & 0xFFF
+    }
+
+    fn skip(&mut self) {
+        self.pc = Self::wrap_addr(self.pc + 2);
+    }
+
+    // Whether `execute` does anything for `opcode`. Everything else, including SuperChip's 00FD
+    // exit and its FX75/FX85 flag registers, runs as a no-op.
+    pub fn is_implemented(opcode: u16) -> bool {
+        match opcode & 0xF000 {
+            0x0000 => matches!(opcode, 0x00E0 | 0x00EE | 0x00FB | 0x00FC | 0x00FE | 0x00FF) || opcode & 0xFFF0 == 0x00C0,
+            0x5000 | 0x9000 => opcode & 0x000F == 0,
+            0x8000 => matches!(opcode & 0x000F, 0x0..=0x7 | 0xE),
+            0xE000 => matches!(opcode & 0x00FF, 0x9E | 0xA1),
+            0xF000 => matches!(opcode & 0x00FF, 0x07 | 0x0A | 0x15 | 0x18 | 0x1E | 0x29 | 0x30 | 0x33 | 0x55 | 0x65),
+            _ => true,
+        }
+    }
+
     pub fn execute(&mut self) {
-        let opcode= ((self.ram[(self.pc & 0xFFF) as usize] as u16) << 8) | (self.ram[((self.pc + 1) & 0xFFF) as usize] as u16);
-        self.pc += 2;
+        let opcode = ((self.ram[self.pc as usize] as u16) << 8) | (self.ram[Self::wrap_addr(self.pc + 1) as usize] as u16);
+
+        if let Some(mut tracer) = self.tracer.take() {
+            tracer.record(self.trace_line(opcode));
+            self.tracer = Some(tracer);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record_exec(self.pc);
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(opcode);
+        }
+        if let Some(write_monitor) = &mut self.write_monitor {
+            write_monitor.record_exec(self.pc);
+        }
+
+        let opcode_pc = self.pc;
+
+        self.pc = Self::wrap_addr(self.pc + 2);
+
+        if !Self::is_implemented(opcode) {
+            return;
+        }
 
         let x = || -> usize { ((opcode & 0x0F00) >> 8) as usize };
         let y = || -> usize { ((opcode & 0x00F0) >> 4) as usize };
@@ -179,19 +514,15 @@ impl ChipCore {
                     }
                     0x00EE => {
                         self.sp = self.sp.wrapping_sub(1) & 0xF;
-                        self.pc = self.stack[self.sp as usize];
+                        self.pc = Self::wrap_addr(self.stack[self.sp as usize]);
                     }
-                    0x00FE => {
-                        if self.high_res_mode {
-                            self.high_res_mode = false;
-                            self.screen_buf.fill(0);   
-                        }
+                    0x00FE if self.high_res_mode => {
+                        self.high_res_mode = false;
+                        self.screen_buf.fill(0);   
                     }
-                    0x00FF => {
-                        if !self.high_res_mode {
-                            self.high_res_mode = true;
-                            self.schip_screen_buf.fill(0);   
-                        }
+                    0x00FF if !self.high_res_mode => {
+                        self.high_res_mode = true;
+                        self.schip_screen_buf.fill(0);   
                     }
                     0x00FB => {
                         if self.high_res_mode {
@@ -217,23 +548,17 @@ impl ChipCore {
                             }
                         }
                     }
-                    _ => {
-                        match opcode & 0xFFF0 {
-                            0x00C0 => {
-                                let n = (opcode & 0x000F) as usize;
-                                
-                                if self.high_res_mode {
-                                    Self::shift_screenbuf_down(&mut self.schip_screen_buf, n);
-                                }
-                                else {
-                                    Self::shift_screenbuf_down(&mut self.screen_buf, n);
-                                }
-                            }
-                            _ => {
-                                println!("Unknown opcode {:X}", opcode);
-                            }
+                    _ if opcode & 0xFFF0 == 0x00C0 => {
+                        let n = (opcode & 0x000F) as usize;
+                        
+                        if self.high_res_mode {
+                            Self::shift_screenbuf_down(&mut self.schip_screen_buf, n);
+                        }
+                        else {
+                            Self::shift_screenbuf_down(&mut self.screen_buf, n);
                         }
                     }
+                    _ => {}
                 }
             }
             0x1000 => {
@@ -244,27 +569,14 @@ impl ChipCore {
                 self.sp = (self.sp + 1) & 0xF;
                 self.pc = addr();
             }
-            0x3000 => {
-                if self.regs[x()] == data() {
-                    self.pc += 2;
-                }
+            0x3000 if self.regs[x()] == data() => {
+                self.skip();
             }
-            0x4000 => {
-                if self.regs[x()] != data() {
-                    self.pc += 2;
-                }
+            0x4000 if self.regs[x()] != data() => {
+                self.skip();
             }
-            0x5000 => {
-                match opcode & 0x000F {
-                    0x0000 => {
-                        if self.regs[x()] == self.regs[y()] {
-                            self.pc += 2;
-                        }
-                    }
-                    _ => {
-                        println!("Unknown opcode {:X}", opcode);
-                    }
-                }
+            0x5000 if opcode & 0x000F == 0 && self.regs[x()] == self.regs[y()] => {
+                self.skip();
             }
             0x6000 => {
                 self.regs[x()] = data();
@@ -279,15 +591,21 @@ impl ChipCore {
                     }
                     0x0001 => {
                         self.regs[x()] |= self.regs[y()];
-                        self.regs[0xF] = 0; // VF reset quirk.
+                        if self.quirks.vf_reset {
+                            self.regs[0xF] = 0;
+                        }
                     }
                     0x0002 => {
                         self.regs[x()] &= self.regs[y()];
-                        self.regs[0xF] = 0; // VF reset quirk.
+                        if self.quirks.vf_reset {
+                            self.regs[0xF] = 0;
+                        }
                     }
                     0x0003 => {
                         self.regs[x()] ^= self.regs[y()];
-                        self.regs[0xF] = 0; // VF reset quirk.
+                        if self.quirks.vf_reset {
+                            self.regs[0xF] = 0;
+                        }
                     }
                     0x0004 => {
                         let (res, overflow) = self.regs[x()].overflowing_add(self.regs[y()]);
@@ -300,9 +618,9 @@ impl ChipCore {
                         self.regs[0xF] = !overflow as u8;
                     }
                     0x0006 => {
-                        let shifted = self.regs[x()] & 0x1;
-                        self.regs[x()] >>= 1;
-                        self.regs[0xF] = shifted;
+                        let value = self.regs[if self.quirks.shift_vy { y() } else { x() }];
+                        self.regs[x()] = value >> 1;
+                        self.regs[0xF] = value & 0x1;
                     }
                     0x0007 => {
                         let (res, overflow) = self.regs[y()].overflowing_sub(self.regs[x()]);
@@ -310,32 +628,22 @@ impl ChipCore {
                         self.regs[0xF] = !overflow as u8;
                     }
                     0x000E => {
-                        let shifted = (self.regs[x()] & 0x80) >> 7;
-                        self.regs[x()] <<= 1;
-                        self.regs[0xF] = shifted;
-                    }
-                    _ => {
-                        println!("Unknown opcode {:X}", opcode);
+                        let value = self.regs[if self.quirks.shift_vy { y() } else { x() }];
+                        self.regs[x()] = value << 1;
+                        self.regs[0xF] = value >> 7;
                     }
+                    _ => {}
                 }
             }
-            0x9000 => {
-                match opcode & 0x000F {
-                    0x0000 => {
-                        if self.regs[x()] != self.regs[y()] {
-                            self.pc += 2;
-                        }
-                    }
-                    _ => {
-                        println!("Unknown opcode {:X}", opcode);
-                    }
-                }
+            0x9000 if opcode & 0x000F == 0 && self.regs[x()] != self.regs[y()] => {
+                self.skip();
             }
             0xA000 => {
                 self.i_reg = addr();
             }
             0xB000 => {
-                self.pc = (self.regs[0] as u16) + addr();
+                let offset = if self.quirks.jump_vx { self.regs[x()] } else { self.regs[0] };
+                self.pc = Self::wrap_addr(offset as u16 + addr());
             }
             0xC000 => {
                 self.regs[x()] = self.rng.gen::<u8>() & data();
@@ -345,19 +653,13 @@ impl ChipCore {
             }
             0xE000 => {
                 match opcode & 0x00FF {
-                    0x009E => {
-                        if self.keys[(self.regs[x()] & 0xF) as usize] {
-                            self.pc += 2;
-                        }
+                    0x009E if self.keys[(self.regs[x()] & 0xF) as usize] => {
+                        self.skip();
                     }
-                    0x00A1 => {
-                        if !self.keys[(self.regs[x()] & 0xF) as usize] {
-                            self.pc += 2;
-                        }
-                    }
-                    _ => {
-                        println!("Unknown opcode {:X}", opcode);
+                    0x00A1 if !self.keys[(self.regs[x()] & 0xF) as usize] => {
+                        self.skip();
                     }
+                    _ => {}
                 }
             }
             0xF000 => {
@@ -366,16 +668,18 @@ impl ChipCore {
                         self.regs[x()] = self.delay_timer;
                     }
                     0x000A => {
-                        if !self.awaiting_key_release {
-                            self.awaiting_key_release = true;
-                            self.released_key_reg = x() as i8;
-                        }
-                        else if self.released_key_reg == -1 {
-                            self.awaiting_key_release = false;
-                            return;
+                        match self.key_wait {
+                            KeyWait::Complete => {
+                                self.key_wait = KeyWait::Idle;
+                                return;
+                            }
+                            KeyWait::Idle => {
+                                self.key_wait = KeyWait::WaitingForPress { reg: x() as u8 };
+                            }
+                            _ => {}
                         }
 
-                        self.pc -= 2;
+                        self.pc = Self::wrap_addr(self.pc.wrapping_sub(2));
                     }
                     0x0015 => {
                         self.delay_timer = self.regs[x()];
@@ -387,34 +691,60 @@ impl ChipCore {
                         self.i_reg = self.i_reg.wrapping_add(self.regs[x()] as u16);
                     }
                     0x0029 => {
-                        self.i_reg = ((self.regs[x()] & 0xF) * 0x5) as u16;
+                        self.i_reg = (self.regs[x()] & 0xF) as u16 * 5;
                     }
                     0x0030 => {
-                        self.i_reg = Self::SCHIP_FONT_OFFSET as u16 + ((self.regs[x()] & 0xF) * 10) as u16;
+                        self.i_reg = Self::SCHIP_FONT_OFFSET as u16 + (self.regs[x()] & 0xF) as u16 * 10;
                     }
                     0x0033 => {
-                        self.ram[self.i_reg as usize & 0xFFF] = self.regs[x()] / 100;
-                        self.ram[(self.i_reg as usize + 1) & 0xFFF] = (self.regs[x()] / 10) % 10;
-                        self.ram[(self.i_reg as usize + 2) & 0xFFF] = self.regs[x()] % 10;
+                        if let Some(coverage) = &mut self.coverage {
+                            for i in 0..3 {
+                                coverage.record_write(self.i_reg.wrapping_add(i));
+                            }
+                        }
+                        if let Some(write_monitor) = &mut self.write_monitor {
+                            for i in 0..3 {
+                                write_monitor.record_write(opcode_pc, self.i_reg.wrapping_add(i));
+                            }
+                        }
+
+                        self.ram[Self::wrap_addr(self.i_reg) as usize] = self.regs[x()] / 100;
+                        self.ram[Self::wrap_addr(self.i_reg.wrapping_add(1)) as usize] = (self.regs[x()] / 10) % 10;
+                        self.ram[Self::wrap_addr(self.i_reg.wrapping_add(2)) as usize] = self.regs[x()] % 10;
                     }
                     0x0055 => {
                         for i in 0..=x() {
-                            self.ram[(self.i_reg as usize + i) & 0xFFF] = self.regs[i];
+                            self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i as u16)) as usize] = self.regs[i];
+
+                            if let Some(coverage) = &mut self.coverage {
+                                coverage.record_write(self.i_reg.wrapping_add(i as u16));
+                            }
+                            if let Some(write_monitor) = &mut self.write_monitor {
+                                write_monitor.record_write(opcode_pc, self.i_reg.wrapping_add(i as u16));
+                            }
+                        }
+
+                        if self.quirks.memory_increment {
+                            self.i_reg = self.i_reg.wrapping_add(x() as u16 + 1);
                         }
                     }
                     0x0065 => {
                         for i in 0..=x() {
-                            self.regs[i] = self.ram[(self.i_reg as usize + i) & 0xFFF];
+                            self.regs[i] = self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i as u16)) as usize];
+
+                            if let Some(coverage) = &mut self.coverage {
+                                coverage.record_read(self.i_reg.wrapping_add(i as u16));
+                            }
+                        }
+
+                        if self.quirks.memory_increment {
+                            self.i_reg = self.i_reg.wrapping_add(x() as u16 + 1);
                         }
                     }
-                    _ => {
-                        println!("Unknown opcode {:X}", opcode);
-                    }
+                    _ => {}
                 }
             }
-            _ => {
-                println!("Unknown opcode {:X}", opcode);
-            }
+            _ => {}
         }
     }
 
@@ -459,13 +789,20 @@ impl ChipCore {
                 break;
             }
 
+            if let Some(coverage) = &mut self.coverage {
+                coverage.record_read(self.i_reg.wrapping_add(i));
+                if DOUBLE_HEIGHT {
+                    coverage.record_read(self.i_reg.wrapping_add(i + 1));
+                }
+            }
+
             let sprite_row = if DOUBLE_HEIGHT {
-                let hi = self.ram[((self.i_reg + i) & 0xFFF) as usize] as u64;
-                let lo = self.ram[((self.i_reg + i + 1) & 0xFFF) as usize] as u64;
+                let hi = self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i)) as usize] as u64;
+                let lo = self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i + 1)) as usize] as u64;
                 (hi << 8) | lo
             }
             else {
-                self.ram[((self.i_reg + i) & 0xFFF) as usize] as u64
+                self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i)) as usize] as u64
             };
 
             let sprite_mask = if x_pos > sprite_bound { sprite_row >> (x_pos - sprite_bound) }
@@ -486,13 +823,20 @@ impl ChipCore {
                 break;
             }
 
+            if let Some(coverage) = &mut self.coverage {
+                coverage.record_read(self.i_reg.wrapping_add(i));
+                if DOUBLE_HEIGHT {
+                    coverage.record_read(self.i_reg.wrapping_add(i + 1));
+                }
+            }
+
             let sprite_row = if DOUBLE_HEIGHT {
-                let hi = self.ram[((self.i_reg + i) & 0xFFF) as usize] as u64;
-                let lo = self.ram[((self.i_reg + i + 1) & 0xFFF) as usize] as u64;
+                let hi = self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i)) as usize] as u64;
+                let lo = self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i + 1)) as usize] as u64;
                 ((hi << 8) | lo) as u128
             }
             else {
-                self.ram[((self.i_reg + i) & 0xFFF) as usize] as u128
+                self.ram[Self::wrap_addr(self.i_reg.wrapping_add(i)) as usize] as u128
             };
 
             let sprite_mask = if x_pos > sprite_bound { sprite_row >> (x_pos - sprite_bound) }
@@ -503,4 +847,7 @@ impl ChipCore {
             y_pos += 1;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
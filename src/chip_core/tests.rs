@@ -0,0 +1,569 @@
+use super::*;
+use crate::lockstep::run_lockstep;
+use crate::write_monitor::WriteKind;
+
+fn chip_with(quirks: Quirks, program: &[u16]) -> ChipCore {
+    let bytes: Vec<u8> = program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    let mut chip = ChipCore::new();
+
+    chip.set_quirks(quirks);
+    assert!(chip.load_rom_bytes(&bytes));
+    chip
+}
+
+fn run(chip: &mut ChipCore, instructions: usize) {
+    for _ in 0..instructions {
+        chip.execute();
+    }
+}
+
+// Runs `program` to completion under every quirk preset and checks the result with `check`.
+fn for_each_preset(program: &[u16], check: impl Fn(&ChipCore, &str)) {
+    for (name, quirks) in Quirks::PRESETS {
+        let mut chip = chip_with(quirks, program);
+        run(&mut chip, program.len());
+        check(&chip, name);
+    }
+}
+
+fn lores_pixel(chip: &ChipCore, x: usize, y: usize) -> bool {
+    (chip.screen_buf[y] >> (ChipCore::CHIP_SCR_WIDTH - 1 - x)) & 1 == 1
+}
+
+fn hires_pixel(chip: &ChipCore, x: usize, y: usize) -> bool {
+    (chip.schip_screen_buf[y] >> (ChipCore::SCHIP_SCR_WIDTH - 1 - x)) & 1 == 1
+}
+
+#[test]
+fn clear_screen() {
+    let mut chip = chip_with(Quirks::default(), &[0x00E0]);
+    chip.screen_buf = [u64::MAX; ChipCore::CHIP_SCR_HEIGHT];
+    run(&mut chip, 1);
+    assert!(chip.screen_buf.iter().all(|&row| row == 0));
+}
+
+#[test]
+fn call_and_return() {
+    // 0x200: CALL 0x206, 0x202: LD V0, 1, 0x206: RET
+    let mut chip = chip_with(Quirks::default(), &[0x2206, 0x6001, 0x0000, 0x00EE]);
+
+    run(&mut chip, 1);
+    assert_eq!((chip.pc, chip.sp, chip.stack[0]), (0x206, 1, 0x202));
+
+    run(&mut chip, 2);
+    assert_eq!((chip.pc, chip.sp, chip.regs[0]), (0x204, 0, 1));
+}
+
+#[test]
+fn call_stack_wraps_after_sixteen_levels() {
+    let mut chip = chip_with(Quirks::default(), &[0x2200]);
+    run(&mut chip, 16);
+    assert_eq!(chip.sp, 0);
+    assert_eq!(chip.pc, 0x200);
+}
+
+#[test]
+fn jumps() {
+    for (name, quirks) in Quirks::PRESETS {
+        // V0 and V3 match, so BNNN lands on 0x302 with and without the jump quirk.
+        let mut chip = chip_with(quirks, &[0x1208, 0x0000, 0x0000, 0x0000, 0x6002, 0x6302, 0xB300]);
+
+        run(&mut chip, 1);
+        assert_eq!(chip.pc, 0x208, "{}", name);
+
+        run(&mut chip, 3);
+        assert_eq!(chip.pc, 0x302, "{}", name);
+    }
+}
+
+#[test]
+fn jump_with_offset_quirk() {
+    for_each_preset(&[0x6001, 0x6305, 0xB310], |chip, name| {
+        let expected = if chip.quirks.jump_vx { 0x315 } else { 0x311 };
+        assert_eq!(chip.pc, expected, "{}", name);
+    });
+}
+
+#[test]
+fn jump_with_offset_wraps_inside_memory() {
+    let mut chip = chip_with(Quirks::default(), &[0x60FF, 0xBFFF]);
+    run(&mut chip, 2);
+    assert_eq!(chip.pc, (0xFF + 0xFFF) & 0xFFF);
+}
+
+#[test]
+fn skips() {
+    let cases: [(&[u16], u16); 8] = [
+        (&[0x6012, 0x3012], 0x206),
+        (&[0x6012, 0x3013], 0x204),
+        (&[0x6012, 0x4013], 0x206),
+        (&[0x6012, 0x4012], 0x204),
+        (&[0x6012, 0x6112, 0x5010], 0x208),
+        (&[0x6012, 0x6113, 0x5010], 0x206),
+        (&[0x6012, 0x6113, 0x9010], 0x208),
+        (&[0x6012, 0x6112, 0x9010], 0x206),
+    ];
+
+    for (program, pc) in cases {
+        for_each_preset(program, |chip, name| assert_eq!(chip.pc, pc, "{:04X?} {}", program, name));
+    }
+}
+
+#[test]
+fn load_and_add_immediate() {
+    for_each_preset(&[0x6AFE, 0x7A03, 0x6F07, 0x7F01], |chip, name| {
+        assert_eq!(chip.regs[0xA], 0x01, "{}", name);
+        // 7XNN never touches VF, even when it wraps or targets VF itself.
+        assert_eq!(chip.regs[0xF], 0x08, "{}", name);
+    });
+}
+
+#[test]
+fn register_logic_and_arithmetic() {
+    let cases: [(u16, u8, u8, u8, u8); 9] = [
+        // (opcode, VA, VB, expected VA, expected VF)
+        // Shifts use VA == VB so the result doesn't depend on the shift quirk.
+        (0x8AB0, 0x12, 0x34, 0x34, 0xAA),
+        (0x8AB4, 0xF0, 0x20, 0x10, 0x01),
+        (0x8AB4, 0x10, 0x20, 0x30, 0x00),
+        (0x8AB5, 0x30, 0x10, 0x20, 0x01),
+        (0x8AB5, 0x10, 0x30, 0xE0, 0x00),
+        (0x8AB6, 0x05, 0x05, 0x02, 0x01),
+        (0x8AB7, 0x10, 0x30, 0x20, 0x01),
+        (0x8AB7, 0x30, 0x10, 0xE0, 0x00),
+        (0x8ABE, 0x81, 0x81, 0x02, 0x01),
+    ];
+
+    for (opcode, a, b, expected, flag) in cases {
+        for_each_preset(&[0x6A00 | a as u16, 0x6B00 | b as u16, 0x6FAA, opcode], |chip, name| {
+            assert_eq!(chip.regs[0xA], expected, "{:04X} {}", opcode, name);
+            assert_eq!(chip.regs[0xF], flag, "{:04X} {}", opcode, name);
+        });
+    }
+}
+
+#[test]
+fn logic_ops_and_vf_reset_quirk() {
+    for (opcode, expected) in [(0x8AB1, 0xFC), (0x8AB2, 0x30), (0x8AB3, 0xCC)] {
+        for_each_preset(&[0x6AF0, 0x6B3C, 0x6FAA, opcode], |chip, name| {
+            let flag = if chip.quirks.vf_reset { 0x00 } else { 0xAA };
+            assert_eq!((chip.regs[0xA], chip.regs[0xF]), (expected, flag), "{:04X} {}", opcode, name);
+        });
+    }
+}
+
+#[test]
+fn shift_quirk() {
+    for (opcode, from_vx, from_vy) in [(0x8AB6, (0x00, 1), (0x40, 1)), (0x8ABE, (0x02, 0), (0x02, 1))] {
+        for_each_preset(&[0x6A01, 0x6B81, opcode], |chip, name| {
+            let expected = if chip.quirks.shift_vy { from_vy } else { from_vx };
+            assert_eq!((chip.regs[0xA], chip.regs[0xF]), expected, "{:04X} {}", opcode, name);
+        });
+    }
+}
+
+#[test]
+fn flag_wins_when_vf_is_the_destination() {
+    // VF = 0xF0 + 0x20 sets the carry, which overwrites the sum.
+    for_each_preset(&[0x6FF0, 0x6120, 0x8F14], |chip, name| assert_eq!(chip.regs[0xF], 1, "{}", name));
+    // VF = 0x10 - 0x20 borrows, so VF ends up 0.
+    for_each_preset(&[0x6F10, 0x6120, 0x8F15], |chip, name| assert_eq!(chip.regs[0xF], 0, "{}", name));
+    // VF used as the source operand.
+    for_each_preset(&[0x6F01, 0x6002, 0x80F4], |chip, name| {
+        assert_eq!((chip.regs[0], chip.regs[0xF]), (3, 0), "{}", name);
+    });
+}
+
+#[test]
+fn load_index_and_random() {
+    for_each_preset(&[0xA123, 0x60FF, 0xC000], |chip, name| {
+        assert_eq!(chip.i_reg, 0x123, "{}", name);
+        assert_eq!(chip.regs[0], 0, "{}", name);
+    });
+
+    let mut chip = chip_with(Quirks::default(), &[0xC10F]);
+    run(&mut chip, 1);
+    assert_eq!(chip.regs[1] & 0xF0, 0);
+}
+
+#[test]
+fn draw_sets_pixels_and_collision_flag() {
+    // The 0 glyph drawn twice at (2, 3) erases itself and reports a collision.
+    let mut chip = chip_with(Quirks::default(), &[0x6002, 0x6103, 0xA000, 0xD015, 0xD015]);
+
+    run(&mut chip, 4);
+    assert_eq!(chip.regs[0xF], 0);
+    assert!(!lores_pixel(&chip, 2, 3));
+    assert!(lores_pixel(&chip, 3, 3));
+    assert!(lores_pixel(&chip, 2, 4));
+
+    run(&mut chip, 1);
+    assert_eq!(chip.regs[0xF], 1);
+    assert!(chip.screen_buf.iter().all(|&row| row == 0));
+}
+
+#[test]
+fn draw_wraps_start_position_and_clips_at_edges() {
+    // Start at (64 + 62, 32 + 30): wrapped to (62, 30), then clipped at the right and bottom.
+    let mut chip = chip_with(Quirks::default(), &[0x607E, 0x613E, 0xA300, 0xD014]);
+    chip.ram[0x300..0x304].fill(0xFF);
+    run(&mut chip, 4);
+
+    assert!(lores_pixel(&chip, 62, 30) && lores_pixel(&chip, 63, 31));
+    assert!(!lores_pixel(&chip, 0, 30) && !lores_pixel(&chip, 62, 0));
+    assert_eq!(chip.screen_buf.iter().map(|row| row.count_ones()).sum::<u32>(), 4);
+}
+
+#[test]
+fn draw_reads_sprite_data_past_end_of_memory() {
+    let mut chip = chip_with(Quirks::default(), &[0xAFFF, 0xD002]);
+    chip.ram[0xFFF] = 0x80;
+    chip.ram[0x000] = 0x80;
+    run(&mut chip, 2);
+
+    assert!(lores_pixel(&chip, 0, 0));
+    assert!(lores_pixel(&chip, 0, 1));
+}
+
+#[test]
+fn hires_mode_and_large_sprites() {
+    let mut chip = chip_with(Quirks::default(), &[0x00FF, 0x607C, 0x6100, 0xA300, 0xD010, 0x00FE]);
+    chip.ram[0x300..0x320].fill(0xFF);
+
+    run(&mut chip, 5);
+    assert!(chip.high_res_mode);
+    // 16x16 sprite at x = 124 is clipped to 4 columns.
+    assert!(hires_pixel(&chip, 124, 0) && hires_pixel(&chip, 127, 15));
+    assert!(!hires_pixel(&chip, 0, 0) && !hires_pixel(&chip, 124, 16));
+    assert_eq!(chip.schip_screen_buf.iter().map(|row| row.count_ones()).sum::<u32>(), 4 * 16);
+
+    run(&mut chip, 1);
+    assert!(!chip.high_res_mode);
+}
+
+#[test]
+fn scroll_right_and_left() {
+    let mut chip = chip_with(Quirks::default(), &[0x00FB, 0x00FC, 0x00FC]);
+    chip.screen_buf[0] = 1 << 63 | 1;
+
+    run(&mut chip, 1);
+    assert!(lores_pixel(&chip, 4, 0));
+    assert_eq!(chip.screen_buf[0].count_ones(), 1, "the rightmost pixel scrolls off");
+
+    run(&mut chip, 2);
+    assert_eq!(chip.screen_buf[0], 0, "pixels scrolled past the left edge are gone");
+}
+
+#[test]
+fn scroll_down_boundaries() {
+    let mut chip = chip_with(Quirks::default(), &[0x00C0, 0x00C5, 0x00CF, 0x00CF]);
+    chip.screen_buf[0] = 1;
+    chip.screen_buf[ChipCore::CHIP_SCR_HEIGHT - 1] = 2;
+
+    run(&mut chip, 1);
+    assert_eq!((chip.screen_buf[0], chip.screen_buf[31]), (1, 2), "00C0 does nothing");
+
+    run(&mut chip, 1);
+    assert_eq!(chip.screen_buf[5], 1);
+    assert_eq!(chip.screen_buf[0], 0);
+    assert!(chip.screen_buf.iter().all(|&row| row != 2), "the bottom row scrolls off");
+
+    run(&mut chip, 2);
+    assert!(chip.screen_buf.iter().all(|&row| row == 0));
+}
+
+#[test]
+fn scroll_down_in_hires() {
+    let mut chip = chip_with(Quirks::default(), &[0x00FF, 0x00C3]);
+    run(&mut chip, 1);
+    chip.schip_screen_buf[60] = 1;
+    run(&mut chip, 1);
+
+    assert_eq!(chip.schip_screen_buf[63], 1);
+}
+
+#[test]
+fn key_skips() {
+    for (key_down, pc_9e, pc_a1) in [(true, 0x206, 0x204), (false, 0x204, 0x206)] {
+        for (opcode, pc) in [(0xE59E, pc_9e), (0xE5A1, pc_a1)] {
+            let mut chip = chip_with(Quirks::default(), &[0x6517, opcode]);
+            // Only the low nibble of VX selects the key.
+            chip.key_event(7, key_down);
+            run(&mut chip, 2);
+            assert_eq!(chip.pc, pc, "{:04X} key down: {}", opcode, key_down);
+        }
+    }
+}
+
+#[test]
+fn timers() {
+    for_each_preset(&[0x6030, 0xF015, 0xF018, 0xF207], |chip, name| {
+        assert_eq!((chip.delay_timer, chip.sound_timer, chip.regs[2]), (0x30, 0x30, 0x30), "{}", name);
+    });
+}
+
+#[test]
+fn timers_tick_at_sixty_hertz_of_cycles() {
+    let mut chip = chip_with(Quirks::default(), &[0x603C, 0xF015, 0x1204]);
+    chip.set_cycle_rate(600);
+
+    // One timer tick every 10 cycles, counted from the first instruction.
+    chip.run_cycles(2);
+    chip.run_cycles(298);
+    assert_eq!(chip.delay_timer, 0x3C - 30);
+
+    chip.run_for(Duration::from_millis(500));
+    assert_eq!(chip.delay_timer, 0);
+    assert_eq!(chip.cycle_count(), 600);
+}
+
+#[test]
+fn key_wait_completes_on_release() {
+    for (name, quirks) in Quirks::PRESETS.iter().filter(|(_, quirks)| quirks.key_wait == KeyWaitMode::Release) {
+        let mut chip = chip_with(*quirks, &[0xF30A]);
+
+        run(&mut chip, 3);
+        assert_eq!(chip.pc, 0x200, "{}", name);
+
+        chip.key_event(9, true);
+        run(&mut chip, 1);
+        assert_eq!(chip.pc, 0x200, "{}", name);
+        assert_eq!(chip.key_wait(), KeyWait::WaitingForRelease { reg: 3, key: 9 }, "{}", name);
+
+        chip.key_event(9, false);
+        run(&mut chip, 1);
+        assert_eq!((chip.pc, chip.regs[3]), (0x202, 9), "{}", name);
+        assert_eq!(chip.key_wait(), KeyWait::Idle, "{}", name);
+    }
+}
+
+#[test]
+fn key_wait_completes_on_press() {
+    let mut chip = chip_with(Quirks::CHIP48, &[0xF30A]);
+
+    run(&mut chip, 1);
+    chip.key_event(0xB, true);
+    run(&mut chip, 1);
+    assert_eq!((chip.pc, chip.regs[3]), (0x202, 0xB));
+}
+
+#[test]
+fn key_wait_ignores_key_held_before_wait() {
+    for (name, quirks) in Quirks::PRESETS {
+        let mut chip = chip_with(quirks, &[0xF30A]);
+
+        chip.key_event(4, true);
+        run(&mut chip, 1);
+        chip.key_event(4, false);
+        run(&mut chip, 1);
+        assert_eq!(chip.pc, 0x200, "{}", name);
+    }
+}
+
+#[test]
+fn vip_buzzer_sounds_while_key_held() {
+    for (quirks, sounds) in [(Quirks::VIP, true), (Quirks::MODERN, false)] {
+        let mut chip = chip_with(quirks, &[0xF30A]);
+
+        run(&mut chip, 1);
+        chip.key_event(1, true);
+        assert_eq!(chip.is_sound_active(), sounds);
+
+        chip.key_event(1, false);
+        assert!(!chip.is_sound_active());
+    }
+}
+
+#[test]
+fn add_to_index_wraps_at_sixteen_bits() {
+    for_each_preset(&[0xAFFF, 0x6010, 0xF01E], |chip, name| assert_eq!(chip.i_reg, 0x100F, "{}", name));
+
+    let mut chip = chip_with(Quirks::default(), &[0x6001, 0xF01E]);
+    chip.i_reg = 0xFFFF;
+    run(&mut chip, 2);
+    assert_eq!(chip.i_reg, 0);
+}
+
+#[test]
+fn font_characters() {
+    for_each_preset(&[0x601F, 0xF029], |chip, name| assert_eq!(chip.i_reg, 0xF * 5, "{}", name));
+    for_each_preset(&[0x601F, 0xF030], |chip, name| {
+        assert_eq!(chip.i_reg, ChipCore::SCHIP_FONT_OFFSET as u16 + 0xF * 10, "{}", name);
+    });
+}
+
+#[test]
+fn binary_coded_decimal() {
+    for (value, digits) in [(255u8, [2, 5, 5]), (0, [0, 0, 0]), (109, [1, 0, 9])] {
+        for_each_preset(&[0x6000 | value as u16, 0xA300, 0xF033], |chip, name| {
+            assert_eq!(chip.ram[0x300..0x303], digits, "{} {}", value, name);
+        });
+    }
+
+    // BCD at the end of memory wraps to address 0.
+    let mut chip = chip_with(Quirks::default(), &[0x60FF, 0xAFFF, 0xF033]);
+    run(&mut chip, 3);
+    assert_eq!([chip.ram[0xFFF], chip.ram[0x000], chip.ram[0x001]], [2, 5, 5]);
+}
+
+#[test]
+fn store_and_load_registers() {
+    let program = [0x6011, 0x6122, 0x6233, 0xA300, 0xF155, 0x6000, 0x6100, 0x6200, 0xA300, 0xF265];
+
+    for_each_preset(&program, |chip, name| {
+        assert_eq!(chip.ram[0x300..0x303], [0x11, 0x22, 0x00], "{}", name);
+        assert_eq!(chip.regs[..3], [0x11, 0x22, 0x00], "{}", name);
+
+        let expected = if chip.quirks.memory_increment { 0x303 } else { 0x300 };
+        assert_eq!(chip.i_reg, expected, "{}", name);
+    });
+}
+
+#[test]
+fn unknown_opcodes_are_skipped() {
+    for_each_preset(&[0x5001, 0x800F, 0xE000, 0xF0FF, 0x0123], |chip, name| {
+        assert_eq!(chip.pc, 0x20A, "{}", name);
+    });
+}
+
+#[test]
+fn program_counter_wraps_at_end_of_memory() {
+    let mut chip = chip_with(Quirks::default(), &[0x1FFE]);
+    run(&mut chip, 2);
+    assert_eq!(chip.pc, 0x000);
+}
+
+#[test]
+fn queued_key_events_apply_at_their_cycle() {
+    let mut chip = chip_with(Quirks::default(), &[0x1200]);
+    chip.set_cycle_rate(600);
+    chip.queue_key_event(5, 2, true);
+    chip.queue_key_event(6, 2, false);
+
+    chip.run_cycles(5);
+    assert!(!chip.get_keys()[2]);
+
+    chip.run_cycles(1);
+    assert!(chip.get_keys()[2]);
+
+    // The release is held back until the key has been down for a 60 Hz frame (10 cycles).
+    chip.run_cycles(9);
+    assert!(chip.get_keys()[2]);
+    chip.run_cycles(1);
+    assert!(!chip.get_keys()[2]);
+}
+
+#[test]
+fn soft_reset_keeps_ram_and_hard_reset_restores_rom() {
+    let mut chip = chip_with(Quirks::default(), &[0x6042, 0xA202, 0xF055, 0x00FF]);
+    run(&mut chip, 4);
+    assert_eq!(chip.ram[0x202], 0x42);
+    chip.queue_key_event(chip.cycle_count(), 5, true);
+    chip.queue_key_event(chip.cycle_count() + 1000, 5, false);
+    chip.run_cycles(1);
+    assert!(chip.keys[5]);
+
+    chip.soft_reset();
+    assert_eq!((chip.pc, chip.regs[0], chip.i_reg, chip.high_res_mode), (0x200, 0, 0, false));
+    assert_eq!(chip.ram[0x202], 0x42);
+    // Pending input from before the reset must not replay into the restarted program.
+    assert!(chip.input_queue.is_empty());
+    assert_eq!((chip.key_schedule, chip.keys), ([0; 16], [false; 16]));
+
+    chip.hard_reset();
+    assert_eq!(chip.ram[0x202..0x204], [0xA2, 0x02]);
+    assert_eq!(chip.rom_size(), 8);
+
+    // Resetting inside a subroutine puts the profiler back in the root routine.
+    let mut chip = chip_with(Quirks::default(), &[0x2206, 0x0000, 0x0000, 0x6001]);
+    chip.set_profiler_enabled(true);
+    run(&mut chip, 2);
+    chip.soft_reset();
+
+    let profiler = chip.profiler_mut().unwrap();
+    profiler.reset_counts();
+    run(&mut chip, 1);
+    assert!(chip.profiler_mut().unwrap().report().contains("\n  200     100.0%  100.0%"));
+}
+
+#[test]
+fn seeded_random_is_deterministic() {
+    let mut left = chip_with(Quirks::default(), &[0xC0FF, 0xC1FF, 0xC2FF]);
+    let mut right = chip_with(Quirks::default(), &[0xC0FF, 0xC1FF, 0xC2FF]);
+    left.set_rng_seed(7);
+    right.set_rng_seed(7);
+
+    run(&mut left, 3);
+    run(&mut right, 3);
+    assert_eq!(left.regs[..3], right.regs[..3]);
+}
+
+#[test]
+fn rng_seed_survives_rom_load_and_hard_reset() {
+    let program: Vec<u8> = [0xC0FFu16, 0xC1FF, 0xC2FF, 0xC3FF].iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    let mut left = ChipCore::new();
+    let mut right = ChipCore::new();
+    left.set_rng_seed(0);
+    right.set_rng_seed(0);
+    assert!(left.load_rom_bytes(&program) && right.load_rom_bytes(&program));
+
+    assert!(run_lockstep(&mut left, &mut right, 4).is_none());
+
+    let first_run = left.regs;
+    left.hard_reset();
+    run(&mut left, 4);
+    assert_eq!(left.regs, first_run);
+}
+
+#[test]
+fn lockstep_reports_first_divergent_instruction() {
+    let program = [0x6A01, 0x6B81, 0x7001, 0x8AB6, 0x7001];
+    let mut modern = chip_with(Quirks::MODERN, &program);
+    let mut vip = chip_with(Quirks::VIP, &program);
+
+    let div = run_lockstep(&mut modern, &mut vip, 5).expect("the shift quirk should diverge");
+    assert_eq!((div.instruction, div.pc, div.opcode), (4, 0x206, 0x8AB6));
+    assert_eq!(div.fields, [("VA".to_string(), "00".to_string(), "40".to_string())]);
+
+    let mut left = chip_with(Quirks::MODERN, &program);
+    let mut right = chip_with(Quirks::MODERN, &program);
+    assert!(run_lockstep(&mut left, &mut right, 5).is_none());
+}
+
+#[test]
+fn write_monitor_flags_code_and_font_writes() {
+    // 200: LD I, 200   202: LD [I], V0   204: LD I, 1F0   206: LD B, V0   208: LD I, 300   20A: LD [I], V0
+    let mut chip = chip_with(Quirks::default(), &[0xA200, 0xF055, 0xA1F0, 0xF033, 0xA300, 0xF055]);
+    chip.set_write_monitor_enabled(true);
+    // Writing A2 back over A200 leaves the program intact for the second run.
+    chip.regs[0] = 0xA2;
+    run(&mut chip, 6);
+
+    let events = chip.write_monitor_mut().unwrap().take_events();
+    let summary: Vec<(u16, u16, WriteKind)> = events.iter().map(|event| (event.pc, event.addr, event.kind)).collect();
+    assert_eq!(summary, [
+        (0x202, 0x200, WriteKind::SelfModifying),
+        (0x206, 0x1F0, WriteKind::Font),
+        (0x206, 0x1F1, WriteKind::Font),
+        (0x206, 0x1F2, WriteKind::Font),
+    ]);
+
+    // Repeats of the same write are only reported once.
+    chip.soft_reset();
+    run(&mut chip, 2);
+    assert!(chip.write_monitor_mut().unwrap().take_events().is_empty());
+}
+
+#[test]
+fn unimplemented_opcodes_only_advance_pc() {
+    for opcode in [0x0123, 0x00FD, 0x5121, 0x8128, 0x912F, 0xE1FF, 0xF175, 0xF185] {
+        assert!(!ChipCore::is_implemented(opcode), "{:04X}", opcode);
+
+        let mut chip = chip_with(Quirks::default(), &[0x6155, opcode]);
+        run(&mut chip, 1);
+        let before = chip.state();
+        run(&mut chip, 1);
+
+        assert_eq!(chip.state(), CoreState { pc: 0x204, ..before }, "{:04X}", opcode);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::chip_core::ChipCore;
+
+pub struct Coverage {
+    exec: Vec<u32>,
+    reads: Vec<u32>,
+    writes: Vec<u32>,
+}
+
+impl Coverage {
+    pub const MAP_SIZE: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            exec: vec![0; ChipCore::RAM_SIZE],
+            reads: vec![0; ChipCore::RAM_SIZE],
+            writes: vec![0; ChipCore::RAM_SIZE],
+        }
+    }
+
+    pub fn record_exec(&mut self, addr: u16) {
+        let count = &mut self.exec[addr as usize & 0xFFF];
+        *count = count.saturating_add(1);
+    }
+    pub fn record_read(&mut self, addr: u16) {
+        let count = &mut self.reads[addr as usize & 0xFFF];
+        *count = count.saturating_add(1);
+    }
+    pub fn record_write(&mut self, addr: u16) {
+        let count = &mut self.writes[addr as usize & 0xFFF];
+        *count = count.saturating_add(1);
+    }
+
+    fn intensity(count: u32, max: u32) -> u32 {
+        if count == 0 || max == 0 {
+            return 0;
+        }
+
+        // Log scale so a hot loop doesn't wash out code that only ran a few times.
+        let scaled = (count as f64).ln_1p() / (max as f64).ln_1p();
+        0x40 + (scaled * (0xFF - 0x40) as f64) as u32
+    }
+
+    // One pixel per address, 64 addresses per row: red = written, green = executed, blue = read.
+    pub fn render_heat_map(&self, buf: &mut [u32]) {
+        let max_exec = self.exec.iter().copied().max().unwrap_or(0);
+        let max_read = self.reads.iter().copied().max().unwrap_or(0);
+        let max_write = self.writes.iter().copied().max().unwrap_or(0);
+
+        for (addr, pixel) in buf.iter_mut().enumerate().take(ChipCore::RAM_SIZE) {
+            *pixel = (Self::intensity(self.writes[addr], max_write) << 16)
+                | (Self::intensity(self.exec[addr], max_exec) << 8)
+                | Self::intensity(self.reads[addr], max_read);
+        }
+    }
+
+    fn classify(&self, addr: usize) -> &'static str {
+        // Only the first byte of an instruction is counted as executed, so the byte after it is code too.
+        if self.exec[addr] > 0 || (addr > 0 && self.exec[addr - 1] > 0) {
+            "code"
+        }
+        else if self.reads[addr] > 0 || self.writes[addr] > 0 {
+            "data"
+        }
+        else {
+            "untouched"
+        }
+    }
+
+    pub fn report(&self, rom_size: usize) -> String {
+        let rom_range = 0x200..(0x200 + rom_size).min(ChipCore::RAM_SIZE);
+        let executed = self.exec.iter().filter(|&&c| c > 0).count();
+        let read = self.reads.iter().filter(|&&c| c > 0).count();
+        let written = self.writes.iter().filter(|&&c| c > 0).count();
+
+        let mut report = format!("Addresses executed: {}\nAddresses read: {}\nAddresses written: {}\n", executed, read, written);
+        report += &format!("\nROM regions ({:03X}-{:03X}):\n", rom_range.start, rom_range.end.saturating_sub(1));
+
+        let mut start = rom_range.start;
+        for addr in rom_range.clone() {
+            if addr + 1 == rom_range.end || self.classify(addr + 1) != self.classify(start) {
+                report += &format!("  {:03X}-{:03X} {}\n", start, addr, self.classify(start));
+                start = addr + 1;
+            }
+        }
+
+        report += "\nExecution counts:\n";
+        for addr in (0..ChipCore::RAM_SIZE).filter(|&addr| self.exec[addr] > 0) {
+            report += &format!("  {:03X} exec {:>10} read {:>10} write {:>10}\n", addr, self.exec[addr], self.reads[addr], self.writes[addr]);
+        }
+
+        report
+    }
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
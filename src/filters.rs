@@ -0,0 +1,238 @@
+// CPU post-processing applied between the core's framebuffer and the window. Every stage
+// takes and returns plain 0RGB buffers so it can be run on its own.
+
+pub struct Image {
+    pub pixels: Vec<u32>,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Scale2x,
+    Scanlines,
+    LcdGrid,
+    Bloom,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 4] = [Filter::Scale2x, Filter::Scanlines, Filter::LcdGrid, Filter::Bloom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::Scale2x => "Scale2x",
+            Filter::Scanlines => "Scanlines",
+            Filter::LcdGrid => "LCD Grid",
+            Filter::Bloom => "Bloom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|filter| filter.name().replace(' ', "").eq_ignore_ascii_case(&name.replace(['-', '_'], "")))
+    }
+}
+
+const LCD_OFF_COLOR: u32 = 0x9BAA8C;
+const LCD_ON_COLOR: u32 = 0x1E2A1C;
+const LCD_GAP_COLOR: u32 = 0x8E9C80;
+
+fn channels(pixel: u32) -> [u32; 3] {
+    [(pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF]
+}
+
+fn from_channels(c: [u32; 3]) -> u32 {
+    (c[0].min(0xFF) << 16) | (c[1].min(0xFF) << 8) | c[2].min(0xFF)
+}
+
+fn lerp_color(from: u32, to: u32, t: u32) -> u32 {
+    let (a, b) = (channels(from), channels(to));
+    from_channels([0, 1, 2].map(|i| (a[i] * (0xFF - t) + b[i] * t) / 0xFF))
+}
+
+fn luminance(pixel: u32) -> u32 {
+    let c = channels(pixel);
+    (c[0] * 3 + c[1] * 6 + c[2]) / 10
+}
+
+pub fn nearest(src: &Image, factor: usize) -> Image {
+    resize_nearest(src, src.width * factor, src.height * factor)
+}
+
+// Nearest neighbour to any size, e.g. from the 2x Scale2x output to an odd pixel size.
+pub fn resize_nearest(src: &Image, width: usize, height: usize) -> Image {
+    let mut pixels = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        let src_row = &src.pixels[(y * src.height / height) * src.width..][..src.width];
+        pixels.extend((0..width).map(|x| src_row[x * src.width / width]));
+    }
+
+    Image { pixels, width, height }
+}
+
+// EPX/Scale2x: doubles the resolution and rounds off diagonal staircases.
+pub fn scale2x(src: &Image) -> Image {
+    let (w, h) = (src.width, src.height);
+    let mut out = Image { pixels: vec![0; w * h * 4], width: w * 2, height: h * 2 };
+    let at = |x: usize, y: usize| src.pixels[y * w + x];
+
+    for y in 0..h {
+        for x in 0..w {
+            let p = at(x, y);
+            let a = at(x, y.saturating_sub(1));
+            let b = at((x + 1).min(w - 1), y);
+            let c = at(x.saturating_sub(1), y);
+            let d = at(x, (y + 1).min(h - 1));
+
+            let e0 = if c == a && c != d && a != b { a } else { p };
+            let e1 = if a == b && a != c && b != d { b } else { p };
+            let e2 = if d == c && d != b && c != a { c } else { p };
+            let e3 = if b == d && b != a && d != c { d } else { p };
+
+            let i = (y * 2) * out.width + x * 2;
+            out.pixels[i] = e0;
+            out.pixels[i + 1] = e1;
+            out.pixels[i + out.width] = e2;
+            out.pixels[i + out.width + 1] = e3;
+        }
+    }
+
+    out
+}
+
+// Darkens the bottom quarter of every `cell`-pixel row, like the gaps between CRT scanlines.
+pub fn scanlines(img: &mut Image, cell: usize) {
+    if cell < 2 {
+        return;
+    }
+
+    let dark_rows = (cell / 4).max(1);
+    for (y, row) in img.pixels.chunks_mut(img.width).enumerate() {
+        if y % cell >= cell - dark_rows {
+            for pixel in row {
+                *pixel = lerp_color(*pixel, 0, 0x60);
+            }
+        }
+    }
+}
+
+// Recolors to the greenish HP48 LCD and leaves a one pixel gap between cells.
+pub fn lcd_grid(img: &mut Image, cell: usize) {
+    for (y, row) in img.pixels.chunks_mut(img.width).enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = if cell >= 3 && (x % cell == cell - 1 || y % cell == cell - 1) {
+                LCD_GAP_COLOR
+            }
+            else {
+                lerp_color(LCD_OFF_COLOR, LCD_ON_COLOR, luminance(*pixel))
+            };
+        }
+    }
+}
+
+fn box_blur_rows(pixels: &[u32], width: usize, height: usize, radius: usize) -> Vec<u32> {
+    let mut out = vec![0; pixels.len()];
+    let window = (radius * 2 + 1) as u32;
+
+    for y in 0..height {
+        let row = &pixels[y * width..][..width];
+        let mut sum = [0u32; 3];
+
+        for &pixel in &row[..=radius.min(width - 1)] {
+            let c = channels(pixel);
+            (0..3).for_each(|i| sum[i] += c[i]);
+        }
+
+        for x in 0..width {
+            out[y * width + x] = from_channels(sum.map(|s| s / window));
+
+            if x + radius + 1 < width {
+                let c = channels(row[x + radius + 1]);
+                (0..3).for_each(|i| sum[i] += c[i]);
+            }
+            if x >= radius {
+                let c = channels(row[x - radius]);
+                (0..3).for_each(|i| sum[i] -= c[i]);
+            }
+        }
+    }
+
+    out
+}
+
+fn transpose(pixels: &[u32], width: usize, height: usize) -> Vec<u32> {
+    let mut out = vec![0; pixels.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            out[x * height + y] = pixels[y * width + x];
+        }
+    }
+    out
+}
+
+// Adds a soft glow around lit pixels: a separable box blur blended back on top.
+pub fn bloom(img: &mut Image, radius: usize) {
+    if radius == 0 || img.width == 0 || img.height == 0 {
+        return;
+    }
+
+    let blurred = box_blur_rows(&img.pixels, img.width, img.height, radius);
+    let blurred = transpose(&blurred, img.width, img.height);
+    let blurred = box_blur_rows(&blurred, img.height, img.width, radius);
+    let blurred = transpose(&blurred, img.height, img.width);
+
+    for (pixel, glow) in img.pixels.iter_mut().zip(blurred) {
+        let (c, g) = (channels(*pixel), channels(glow));
+        *pixel = from_channels([0, 1, 2].map(|i| c[i] + g[i] * 3 / 8));
+    }
+}
+
+#[derive(Default)]
+pub struct FilterChain {
+    enabled: Vec<Filter>,
+}
+
+impl FilterChain {
+    pub fn is_enabled(&self, filter: Filter) -> bool {
+        self.enabled.contains(&filter)
+    }
+
+    pub fn toggle(&mut self, filter: Filter) {
+        if let Some(pos) = self.enabled.iter().position(|&f| f == filter) {
+            self.enabled.remove(pos);
+        }
+        else {
+            self.enabled.push(filter);
+        }
+    }
+
+    // Scales `pixels` up by `pixel_size` and runs the enabled stages in a fixed order:
+    // Scale2x, LCD grid, scanlines, bloom. At odd pixel sizes the Scale2x output is resampled,
+    // so its cells come out one pixel uneven.
+    pub fn apply(&self, pixels: &[u32], width: usize, height: usize, pixel_size: usize) -> Image {
+        let src = Image { pixels: pixels.to_vec(), width, height };
+
+        let mut img = if self.is_enabled(Filter::Scale2x) {
+            resize_nearest(&scale2x(&src), width * pixel_size, height * pixel_size)
+        }
+        else {
+            nearest(&src, pixel_size)
+        };
+
+        if self.is_enabled(Filter::LcdGrid) {
+            lcd_grid(&mut img, pixel_size);
+        }
+        if self.is_enabled(Filter::Scanlines) {
+            scanlines(&mut img, pixel_size);
+        }
+        if self.is_enabled(Filter::Bloom) {
+            bloom(&mut img, (pixel_size / 2).max(1));
+        }
+
+        img
+    }
+}
+
+#[cfg(test)]
+mod tests;
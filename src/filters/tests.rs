@@ -0,0 +1,107 @@
+use super::*;
+
+const W: u32 = 0xFFFFFF;
+const B: u32 = 0x000000;
+
+fn image(width: usize, height: usize, pixels: &[u32]) -> Image {
+    Image { pixels: pixels.to_vec(), width, height }
+}
+
+fn at(img: &Image, x: usize, y: usize) -> u32 {
+    img.pixels[y * img.width + x]
+}
+
+fn diagonal() -> Image {
+    image(3, 3, &[
+        W, B, B,
+        B, W, B,
+        B, B, W,
+    ])
+}
+
+#[test]
+fn nearest_repeats_pixels() {
+    let out = nearest(&image(2, 1, &[W, B]), 2);
+    assert_eq!((out.width, out.height), (4, 2));
+    assert_eq!(out.pixels, [W, W, B, B, W, W, B, B]);
+}
+
+#[test]
+fn scale2x_rounds_off_diagonals() {
+    let out = scale2x(&diagonal());
+    assert_eq!((out.width, out.height), (6, 6));
+
+    // The background cells next to the diagonal get their inner corner filled in...
+    assert_eq!(at(&out, 2, 1), W);
+    assert_eq!(at(&out, 1, 2), W);
+    // ...while the rest of those cells and the diagonal itself stay as they were.
+    assert_eq!((at(&out, 3, 0), at(&out, 2, 0), at(&out, 3, 1)), (B, B, B));
+    assert_eq!((at(&out, 2, 2), at(&out, 3, 3)), (W, W));
+    assert_eq!(at(&out, 5, 0), B);
+}
+
+#[test]
+fn scale2x_keeps_flat_areas() {
+    let out = scale2x(&image(2, 2, &[W; 4]));
+    assert!(out.pixels.iter().all(|&pixel| pixel == W));
+}
+
+#[test]
+fn scanlines_darken_the_bottom_of_each_cell() {
+    let mut img = image(1, 8, &[W; 8]);
+    scanlines(&mut img, 4);
+    assert_eq!(img.pixels, [W, W, W, 0x9F9F9F, W, W, W, 0x9F9F9F]);
+
+    let mut img = image(1, 2, &[W; 2]);
+    scanlines(&mut img, 1);
+    assert_eq!(img.pixels, [W, W]);
+}
+
+#[test]
+fn lcd_grid_recolors_and_leaves_gaps() {
+    let mut img = image(3, 3, &[W, B, W, W, B, W, W, W, W]);
+    lcd_grid(&mut img, 3);
+
+    assert_eq!((at(&img, 0, 0), at(&img, 1, 0)), (LCD_ON_COLOR, LCD_OFF_COLOR));
+    assert_eq!((at(&img, 2, 0), at(&img, 0, 2)), (LCD_GAP_COLOR, LCD_GAP_COLOR));
+}
+
+#[test]
+fn bloom_spreads_into_neighbours_at_the_edges() {
+    let mut img = image(5, 1, &[W, B, B, B, W]);
+    bloom(&mut img, 1);
+
+    // Lit pixels stay saturated and the glow falls off after one pixel on both edges.
+    assert_eq!(img.pixels[0], W);
+    assert_eq!(img.pixels[4], W);
+    assert!(img.pixels[1] != B && img.pixels[1] == img.pixels[3]);
+    assert_eq!(img.pixels[2], B);
+}
+
+#[test]
+fn bloom_radius_zero_is_a_no_op() {
+    let mut img = diagonal();
+    bloom(&mut img, 0);
+    assert_eq!(img.pixels, diagonal().pixels);
+}
+
+#[test]
+fn chain_scales_without_filters() {
+    let out = FilterChain::default().apply(&[W, B], 2, 1, 3);
+    assert_eq!((out.width, out.height), (6, 3));
+    assert_eq!(&out.pixels[..6], [W, W, W, B, B, B]);
+}
+
+#[test]
+fn chain_applies_scale2x_at_odd_pixel_sizes() {
+    let mut chain = FilterChain::default();
+    chain.toggle(Filter::Scale2x);
+
+    let src = diagonal();
+    let even = chain.apply(&src.pixels, 3, 3, 2);
+    assert_eq!(even.pixels, scale2x(&src).pixels);
+
+    let odd = chain.apply(&src.pixels, 3, 3, 3);
+    assert_eq!((odd.width, odd.height), (9, 9));
+    assert_ne!(odd.pixels, nearest(&src, 3).pixels, "the diagonal should still be smoothed");
+}
@@ -0,0 +1,56 @@
+use crate::coverage::Coverage;
+use crate::text;
+
+// Coverage heat map drawn over the top right corner of the window, below the pause label.
+pub struct HeatMap {
+    visible: bool,
+    pixels: Vec<u32>,
+}
+
+impl HeatMap {
+    const CELL_SIZE: usize = 2;
+    const MARGIN: usize = 4;
+    const TOP: usize = 20;
+    const PANEL_COLOR: u32 = 0x202020;
+    const LEGEND: [(&'static str, u32); 3] = [("WRITE", 0xFF4040), ("EXEC", 0x40FF40), ("READ", 0x4040FF)];
+
+    pub fn new() -> Self {
+        Self { visible: false, pixels: vec![0; Coverage::MAP_SIZE * Coverage::MAP_SIZE] }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn draw(&mut self, buf: &mut [u32], width: usize, height: usize, coverage: Option<&Coverage>) {
+        let Some(coverage) = coverage.filter(|_| self.visible) else {
+            return;
+        };
+        coverage.render_heat_map(&mut self.pixels);
+
+        let scale = (height / 240).max(1);
+        let cell = Self::CELL_SIZE * scale;
+        let size = Coverage::MAP_SIZE * cell;
+        let left = width.saturating_sub(size + Self::MARGIN * scale);
+        let top = Self::TOP * scale;
+        let legend_height = (text::LINE_HEIGHT + 1) * scale;
+
+        text::fill_rect(buf, width, left.saturating_sub(scale), top - scale, size + scale * 2, size + legend_height + scale * 2, Self::PANEL_COLOR);
+
+        for (addr, &color) in self.pixels.iter().enumerate() {
+            let x = left + (addr % Coverage::MAP_SIZE) * cell;
+            let y = top + (addr / Coverage::MAP_SIZE) * cell;
+            text::fill_rect(buf, width, x, y, cell, cell, color);
+        }
+
+        let mut x = left;
+        for (label, color) in Self::LEGEND {
+            text::draw_text(buf, width, x, top + size + scale * 2, label, color, scale);
+            x += text::text_width(label, scale) + text::CHAR_ADVANCE * scale;
+        }
+    }
+}
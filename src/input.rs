@@ -0,0 +1,25 @@
+use minifb::{ InputCallback, Key };
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+// Collects every key press and release minifb reports, with the time it arrived, so events
+// that start and end within one frame are not lost to once-per-frame polling.
+#[derive(Clone, Default)]
+pub struct KeyEventQueue {
+    events: Rc<RefCell<Vec<(Instant, Key, bool)>>>,
+}
+
+impl KeyEventQueue {
+    pub fn drain(&self) -> Vec<(Instant, Key, bool)> {
+        std::mem::take(&mut self.events.borrow_mut())
+    }
+}
+
+impl InputCallback for KeyEventQueue {
+    fn add_char(&mut self, _uni_char: u32) {}
+
+    fn set_key_state(&mut self, key: Key, state: bool) {
+        self.events.borrow_mut().push((Instant::now(), key, state));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::text;
+
+// On-screen COSMAC VIP style hex keypad drawn over the bottom right corner of the window.
+// Keys light up while held and can be pressed with the mouse.
+pub struct Keypad {
+    visible: bool,
+}
+
+impl Keypad {
+    const LAYOUT: [[u8; 4]; 4] = [
+        [0x1, 0x2, 0x3, 0xC],
+        [0x4, 0x5, 0x6, 0xD],
+        [0x7, 0x8, 0x9, 0xE],
+        [0xA, 0x0, 0xB, 0xF],
+    ];
+    const CELL_SIZE: usize = 16;
+    const GAP: usize = 1;
+    const MARGIN: usize = 4;
+    const PANEL_COLOR: u32 = 0x202020;
+    const KEY_COLOR: u32 = 0x505050;
+    const PRESSED_COLOR: u32 = 0xE0A020;
+    const LABEL_COLOR: u32 = 0xFFFFFF;
+    const HINT_COLOR: u32 = 0xA0A0A0;
+
+    pub fn new() -> Self {
+        Self { visible: false }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    // Top left corner of the key grid and the size of one key, in window pixels.
+    fn geometry(width: usize, height: usize) -> (usize, usize, usize) {
+        let scale = (height / 240).max(1);
+        let cell = Self::CELL_SIZE * scale;
+        let grid = cell * 4 + Self::GAP * scale * 3;
+        let margin = Self::MARGIN * scale;
+
+        (width.saturating_sub(grid + margin), height.saturating_sub(grid + margin), cell)
+    }
+
+    pub fn key_at(&self, width: usize, height: usize, x: usize, y: usize) -> Option<u8> {
+        if !self.visible {
+            return None;
+        }
+
+        let (left, top, cell) = Self::geometry(width, height);
+        let stride = cell + Self::GAP * (height / 240).max(1);
+        let (col, row) = ((x.checked_sub(left)?) / stride, (y.checked_sub(top)?) / stride);
+
+        if col >= 4 || row >= 4 || (x - left) % stride >= cell || (y - top) % stride >= cell {
+            return None;
+        }
+        Some(Self::LAYOUT[row][col])
+    }
+
+    // `hints` are the keyboard keys bound to each CHIP-8 key, drawn small in the corner.
+    pub fn draw(&self, buf: &mut [u32], width: usize, height: usize, keys: &[bool; 16], hints: &[String; 16]) {
+        if !self.visible {
+            return;
+        }
+
+        let scale = (height / 240).max(1);
+        let (left, top, cell) = Self::geometry(width, height);
+        let stride = cell + Self::GAP * scale;
+
+        text::fill_rect(buf, width, left.saturating_sub(scale), top.saturating_sub(scale), stride * 4 + scale, stride * 4 + scale, Self::PANEL_COLOR);
+
+        for (row, keys_row) in Self::LAYOUT.iter().enumerate() {
+            for (col, &key) in keys_row.iter().enumerate() {
+                let x = left + col * stride;
+                let y = top + row * stride;
+                let color = if keys[key as usize] { Self::PRESSED_COLOR } else { Self::KEY_COLOR };
+                text::fill_rect(buf, width, x, y, cell, cell, color);
+
+                let label_scale = scale * 2;
+                let label_x = x + (cell - text::GLYPH_WIDTH * label_scale) / 2;
+                let label_y = y + (cell - text::GLYPH_HEIGHT * label_scale) / 2;
+                text::draw_text(buf, width, label_x, label_y, &format!("{:X}", key), Self::LABEL_COLOR, label_scale);
+                text::draw_text(buf, width, x + scale, y + scale, &hints[key as usize], Self::HINT_COLOR, scale);
+            }
+        }
+    }
+}
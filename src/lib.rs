@@ -0,0 +1,11 @@
+// The emulator core and its debugging hooks, usable without the minifb frontend.
+pub mod chip_core;
+pub mod disasm;
+pub mod tracer;
+pub mod coverage;
+pub mod profiler;
+pub mod quirks;
+pub mod lockstep;
+pub mod analyzer;
+pub mod platform;
+pub mod write_monitor;
@@ -0,0 +1,131 @@
+use crate::chip_core::{ ChipCore, KeyWait };
+use crate::disasm;
+
+// Everything that can influence later instructions, captured between two instructions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CoreState {
+    pub pc: u16,
+    pub i_reg: u16,
+    pub sp: u16,
+    pub regs: [u8; 16],
+    pub stack: [u16; 16],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub keys: [bool; 16],
+    pub key_wait: KeyWait,
+    pub high_res_mode: bool,
+    pub ram: Vec<u8>,
+    pub screen: Vec<u64>,
+    pub schip_screen: Vec<u128>,
+}
+
+impl CoreState {
+    // Every field that differs as (field, left, right), in the same shape as a trace divergence.
+    pub fn diff(&self, other: &CoreState) -> Vec<(String, String, String)> {
+        let mut fields = Vec::new();
+        let mut check = |field: String, left: String, right: String| {
+            if left != right {
+                fields.push((field, left, right));
+            }
+        };
+
+        check("PC".to_string(), format!("{:04X}", self.pc), format!("{:04X}", other.pc));
+        for i in 0..16 {
+            check(format!("V{:X}", i), format!("{:02X}", self.regs[i]), format!("{:02X}", other.regs[i]));
+        }
+        check("I".to_string(), format!("{:04X}", self.i_reg), format!("{:04X}", other.i_reg));
+        check("SP".to_string(), format!("{:02X}", self.sp), format!("{:02X}", other.sp));
+        for i in 0..16 {
+            check(format!("STACK[{:X}]", i), format!("{:04X}", self.stack[i]), format!("{:04X}", other.stack[i]));
+        }
+        check("DT".to_string(), format!("{:02X}", self.delay_timer), format!("{:02X}", other.delay_timer));
+        check("ST".to_string(), format!("{:02X}", self.sound_timer), format!("{:02X}", other.sound_timer));
+        for i in 0..16 {
+            check(format!("KEY{:X}", i), self.keys[i].to_string(), other.keys[i].to_string());
+        }
+        check("KEY_WAIT".to_string(), format!("{:?}", self.key_wait), format!("{:?}", other.key_wait));
+        check("HIRES".to_string(), self.high_res_mode.to_string(), other.high_res_mode.to_string());
+
+        for (addr, (l, r)) in self.ram.iter().zip(&other.ram).enumerate() {
+            check(format!("RAM[{:03X}]", addr), format!("{:02X}", l), format!("{:02X}", r));
+        }
+        for (row, (l, r)) in self.screen.iter().zip(&other.screen).enumerate() {
+            check(format!("SCREEN[{}]", row), format!("{:016X}", l), format!("{:016X}", r));
+        }
+        for (row, (l, r)) in self.schip_screen.iter().zip(&other.schip_screen).enumerate() {
+            check(format!("SCHIP_SCREEN[{}]", row), format!("{:032X}", l), format!("{:032X}", r));
+        }
+
+        fields
+    }
+}
+
+// A core that can be run in lockstep against another one, e.g. a faster implementation
+// checked against the reference interpreter.
+pub trait LockstepCore {
+    // Runs exactly one instruction, including queued input and timer ticks.
+    fn step(&mut self);
+    fn queue_key_event(&mut self, cycle: u64, key: u8, pressed: bool);
+    fn state(&self) -> CoreState;
+}
+
+impl LockstepCore for ChipCore {
+    fn step(&mut self) {
+        self.run_cycles(1);
+    }
+
+    fn queue_key_event(&mut self, cycle: u64, key: u8, pressed: bool) {
+        ChipCore::queue_key_event(self, cycle, key, pressed);
+    }
+
+    fn state(&self) -> CoreState {
+        ChipCore::state(self)
+    }
+}
+
+pub struct LockstepDivergence {
+    // Number of instructions both cores ran, the last one being where they split.
+    pub instruction: u64,
+    // The diverging instruction as the left core saw it.
+    pub pc: u16,
+    pub opcode: u16,
+    pub fields: Vec<(String, String, String)>,
+}
+
+impl LockstepDivergence {
+    pub fn describe(&self) -> String {
+        format!("PC:{:04X} OP:{:04X} ; {}", self.pc, self.opcode, disasm::disassemble(self.opcode))
+    }
+}
+
+// Steps both cores one instruction at a time for up to `instructions` instructions and stops at
+// the first one after which their state differs. Instruction 0 means they differed up front.
+pub fn run_lockstep(left: &mut impl LockstepCore, right: &mut impl LockstepCore, instructions: u64) -> Option<LockstepDivergence> {
+    let mut left_state = left.state();
+    let mut right_state = right.state();
+
+    if left_state != right_state {
+        let fields = left_state.diff(&right_state);
+        return Some(LockstepDivergence { instruction: 0, pc: left_state.pc, opcode: opcode_at(&left_state), fields });
+    }
+
+    for instruction in 1..=instructions {
+        let (pc, opcode) = (left_state.pc, opcode_at(&left_state));
+
+        left.step();
+        right.step();
+        left_state = left.state();
+        right_state = right.state();
+
+        if left_state != right_state {
+            return Some(LockstepDivergence { instruction, pc, opcode, fields: left_state.diff(&right_state) });
+        }
+    }
+
+    None
+}
+
+fn opcode_at(state: &CoreState) -> u16 {
+    let pc = state.pc as usize & 0xFFF;
+    ((state.ram[pc] as u16) << 8) | state.ram[(pc + 1) & 0xFFF] as u16
+}
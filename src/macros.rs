@@ -0,0 +1,121 @@
+// Autofire and recorded input macros. Both only decide when CHIP-8 keys go up and down;
+// the App queues the resulting changes into the core like any other key event.
+
+pub struct Autofire {
+    keys: [bool; 16],
+    rate_hz: u32,
+}
+
+impl Autofire {
+    pub const DEFAULT_RATE: u32 = 10;
+    // The core holds every press for at least a frame, so a half period can't be shorter.
+    const MAX_RATE: u32 = 30;
+
+    pub fn new() -> Self {
+        Self { keys: [false; 16], rate_hz: Self::DEFAULT_RATE }
+    }
+
+    pub fn is_enabled(&self, key: usize) -> bool {
+        self.keys[key]
+    }
+
+    pub fn set_enabled(&mut self, key: usize, enabled: bool) {
+        self.keys[key] = enabled;
+    }
+
+    pub fn clear(&mut self) {
+        self.keys = [false; 16];
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate_hz
+    }
+
+    pub fn set_rate(&mut self, rate_hz: u32) {
+        self.rate_hz = rate_hz.clamp(1, Self::MAX_RATE);
+    }
+
+    fn half_period(&self, cycle: u64, cycle_rate: u32) -> u64 {
+        cycle * self.rate_hz as u64 * 2 / cycle_rate as u64
+    }
+
+    // Held autofire keys are down during the first half of every period.
+    pub fn is_down_phase(&self, cycle: u64, cycle_rate: u32) -> bool {
+        self.half_period(cycle, cycle_rate) & 1 == 0
+    }
+
+    // Cycles in `start..end` where autofire keys flip between down and up.
+    pub fn toggle_points(&self, start: u64, end: u64, cycle_rate: u32) -> Vec<u64> {
+        let period_cycles = |half: u64| (half * cycle_rate as u64).div_ceil(self.rate_hz as u64 * 2);
+
+        (self.half_period(start, cycle_rate) + 1..)
+            .map(period_cycles)
+            .take_while(|&cycle| cycle < end)
+            .collect()
+    }
+}
+
+pub struct InputMacro {
+    // (cycles since the start of the macro, key, pressed)
+    events: Vec<(u64, u8, bool)>,
+}
+
+pub struct MacroRecorder {
+    start_cycle: u64,
+    keys: [bool; 16],
+    events: Vec<(u64, u8, bool)>,
+}
+
+impl MacroRecorder {
+    pub fn new(start_cycle: u64) -> Self {
+        Self { start_cycle, keys: [false; 16], events: Vec::new() }
+    }
+
+    // Records the state of `key` at `cycle`, ignoring repeats of the current state.
+    pub fn record(&mut self, cycle: u64, key: u8, pressed: bool) {
+        if self.keys[key as usize] != pressed {
+            self.keys[key as usize] = pressed;
+            self.events.push((cycle.saturating_sub(self.start_cycle), key, pressed));
+        }
+    }
+
+    // Keys still held when recording stops are released at the end of the macro.
+    pub fn finish(mut self, end_cycle: u64) -> InputMacro {
+        for key in 0..16 {
+            self.record(end_cycle, key, false);
+        }
+        InputMacro { events: self.events }
+    }
+}
+
+pub struct MacroPlayback {
+    start_cycle: u64,
+    next: usize,
+}
+
+impl MacroPlayback {
+    pub fn new(start_cycle: u64) -> Self {
+        Self { start_cycle, next: 0 }
+    }
+
+    // Events of `input_macro` that fall before `end_cycle`, with absolute cycle indices.
+    pub fn due(&mut self, input_macro: &InputMacro, end_cycle: u64) -> Vec<(u64, u8, bool)> {
+        let mut due = Vec::new();
+
+        while let Some(&(offset, key, pressed)) = input_macro.events.get(self.next) {
+            if self.start_cycle + offset >= end_cycle {
+                break;
+            }
+            due.push((self.start_cycle + offset, key, pressed));
+            self.next += 1;
+        }
+        due
+    }
+
+    pub fn is_finished(&self, input_macro: &InputMacro) -> bool {
+        self.next >= input_macro.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use super::*;
+use crate::chip_core::ChipCore;
+
+fn looping_chip() -> ChipCore {
+    let mut chip = ChipCore::new();
+    chip.load_rom_bytes(&[0x12, 0x00]);
+    chip
+}
+
+#[test]
+fn recording_keeps_its_timing_across_a_hard_reset() {
+    let mut chip = looping_chip();
+    chip.run_cycles(500);
+
+    let mut recorder = MacroRecorder::new(chip.cycle_count());
+    chip.run_cycles(50);
+    recorder.record(chip.cycle_count(), 1, true);
+    chip.hard_reset();
+    chip.run_cycles(50);
+    recorder.record(chip.cycle_count(), 1, false);
+
+    assert_eq!(recorder.finish(chip.cycle_count()).events, [(50, 1, true), (100, 1, false)]);
+}
+
+#[test]
+fn playback_keeps_its_timing_across_a_hard_reset() {
+    let input_macro = InputMacro { events: vec![(100, 5, true), (300, 5, false)] };
+    let mut chip = looping_chip();
+    chip.run_cycles(1000);
+
+    let mut playback = MacroPlayback::new(chip.cycle_count());
+    assert_eq!(playback.due(&input_macro, chip.cycle_count() + 200), [(1100, 5, true)]);
+    chip.run_cycles(200);
+
+    chip.hard_reset();
+    assert_eq!(playback.due(&input_macro, chip.cycle_count() + 200), [(1300, 5, false)]);
+    assert!(playback.is_finished(&input_macro));
+}
+
+#[test]
+fn autofire_alternates_at_its_rate() {
+    let mut autofire = Autofire::new();
+    autofire.set_enabled(3, true);
+
+    // 10 Hz at 600 Hz is 30 cycles down, then 30 up.
+    assert!(autofire.is_down_phase(0, 600) && autofire.is_down_phase(29, 600));
+    assert!(!autofire.is_down_phase(30, 600) && autofire.is_down_phase(60, 600));
+    assert_eq!(autofire.toggle_points(0, 100, 600), [30, 60, 90]);
+}
@@ -1,8 +1,306 @@
-mod chip_core;
 mod app;
+mod text;
+mod memory_viewer;
+mod sprite_viewer;
+mod octo;
+mod rom_watcher;
+mod capture;
+mod filters;
+mod pacer;
+mod osd;
+mod keypad;
+mod heat_map;
+mod input;
+mod macros;
 
+use chiprust8::{ chip_core, tracer, coverage, quirks, lockstep, platform, write_monitor };
+use chiprust8::analyzer::Analysis;
+use chip_core::ChipCore;
 use app::App;
+use filters::Filter;
+use quirks::Quirks;
+use platform::Platform;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use tracer::Tracer;
+
+fn trace_diff(left_path: &str, right_path: &str) {
+    let (left, right) = match (fs::read_to_string(left_path), fs::read_to_string(right_path)) {
+        (Ok(left), Ok(right)) => (left, right),
+        _ => {
+            println!("Failed to read traces {} and {}", left_path, right_path);
+            return;
+        }
+    };
+
+    match tracer::diff_traces(&left, &right) {
+        None => println!("Traces match ({} lines)", left.lines().count()),
+        Some(div) => {
+            println!("First divergence at line {}", div.line);
+
+            for (field, l, r) in &div.fields {
+                println!("  {}: {} vs {}", field, l, r);
+            }
+            if div.fields.is_empty() && div.left.is_some() && div.right.is_some() {
+                println!("  no fields in common");
+            }
+
+            println!("< {}", div.left.as_deref().unwrap_or("(end of trace)"));
+            println!("> {}", div.right.as_deref().unwrap_or("(end of trace)"));
+        }
+    }
+}
+
+fn lockstep(args: &[String]) {
+    const USAGE: &str = "Usage: lockstep <rom> <left quirks> <right quirks> [--instructions N] [--seed N] [--cycle-rate HZ] [--key CYCLE:KEY:down|up]...";
+
+    let [rom_path, left_quirks, right_quirks, options @ ..] = args else {
+        println!("{}", USAGE);
+        return;
+    };
+
+    let Ok(rom) = fs::read(rom_path) else {
+        println!("Failed to load {}", rom_path);
+        return;
+    };
+
+    let mut cores = Vec::new();
+    for name in [left_quirks, right_quirks] {
+        let Some(quirks) = Quirks::preset(name) else {
+            println!("Unknown quirks {}, expected one of modern, vip, chip48 or xochip", name);
+            return;
+        };
+
+        let mut chip = ChipCore::new();
+        chip.set_quirks(quirks);
+        chip.set_rng_seed(0);
+        if !chip.load_rom_bytes(&rom) {
+            println!("Failed to load {}", rom_path);
+            return;
+        }
+        cores.push(chip);
+    }
+
+    let mut instructions = 1_000_000;
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        let value = options.next();
+
+        match option.as_str() {
+            "--instructions" => match value.and_then(|count| count.parse().ok()) {
+                Some(count) => instructions = count,
+                None => println!("--instructions expects a count"),
+            },
+            "--seed" => match value.and_then(|seed| seed.parse().ok()) {
+                Some(seed) => cores.iter_mut().for_each(|chip| chip.set_rng_seed(seed)),
+                None => println!("--seed expects a number"),
+            },
+            "--cycle-rate" => match value.and_then(|rate| rate.parse().ok()) {
+                Some(rate) => cores.iter_mut().for_each(|chip| chip.set_cycle_rate(rate)),
+                None => println!("--cycle-rate expects a rate in Hz"),
+            },
+            "--key" => {
+                let event = value.and_then(|event| {
+                    let mut parts = event.split(':');
+                    let cycle: u64 = parts.next()?.parse().ok()?;
+                    let key = u8::from_str_radix(parts.next()?, 16).ok().filter(|&key| key < 16)?;
+                    let pressed = match parts.next()? {
+                        "down" => true,
+                        "up" => false,
+                        _ => return None,
+                    };
+                    Some((cycle, key, pressed))
+                });
+
+                match event {
+                    Some((cycle, key, pressed)) => cores.iter_mut().for_each(|chip| chip.queue_key_event(cycle, key, pressed)),
+                    None => println!("--key expects CYCLE:KEY:down or CYCLE:KEY:up, e.g. 120:5:down"),
+                }
+            }
+            _ => println!("Unknown argument {}", option),
+        }
+    }
+
+    let [left, right] = &mut cores[..] else {
+        return;
+    };
+
+    match lockstep::run_lockstep(left, right, instructions) {
+        None => println!("Cores agree for {} instructions", instructions),
+        Some(div) => {
+            println!("First divergence at instruction {}: {}", div.instruction, div.describe());
+
+            for (field, l, r) in &div.fields {
+                println!("  {}: {} vs {}", field, l, r);
+            }
+        }
+    }
+}
+
+fn analyze(args: &[String], listing: bool) {
+    let (rom_path, dot_path) = match args {
+        [rom_path] => (rom_path, None),
+        [rom_path, option, dot_path] if !listing && option == "--dot" => (rom_path, Some(dot_path)),
+        _ => {
+            println!("{}", if listing { "Usage: disasm <rom>" } else { "Usage: analyze <rom> [--dot <file>]" });
+            return;
+        }
+    };
+
+    let Ok(rom) = fs::read(rom_path) else {
+        println!("Failed to load {}", rom_path);
+        return;
+    };
+
+    let analysis = Analysis::new(&rom);
+
+    if listing {
+        print!("{}", analysis.listing());
+        return;
+    }
+
+    print!("{}", analysis.report());
+    println!("\nPlatform: {}", Platform::detect(&rom).describe());
+
+    if let Some(dot_path) = dot_path {
+        match fs::write(dot_path, analysis.to_dot()) {
+            Ok(()) => println!("Control flow graph written to {}", dot_path),
+            Err(e) => println!("Failed to write {}: {}", dot_path, e),
+        }
+    }
+}
 
 fn main() {
-    App::new().run();
-}
\ No newline at end of file
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("trace-diff") {
+        if args.len() != 3 {
+            println!("Usage: trace-diff <left trace> <right trace>");
+            return;
+        }
+
+        trace_diff(&args[1], &args[2]);
+        return;
+    }
+
+    if args.first().map(String::as_str) == Some("lockstep") {
+        lockstep(&args[1..]);
+        return;
+    }
+
+    if let Some(command @ ("analyze" | "disasm")) = args.first().map(String::as_str) {
+        analyze(&args[1..], command == "disasm");
+        return;
+    }
+
+    let mut app = App::new();
+    let mut args = args.iter();
+    let mut rom = None;
+    let mut cycle_rate = None;
+    let mut quirks = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => {
+                match args.next().map(|path| Tracer::to_file(Path::new(path))) {
+                    Some(Ok(tracer)) => app.set_tracer(tracer),
+                    Some(Err(e)) => println!("Failed to open trace file: {}", e),
+                    None => println!("--trace expects a file path"),
+                }
+            }
+            "--trace-ring" => {
+                match args.next().and_then(|lines| lines.parse().ok()) {
+                    Some(lines) => app.set_tracer(Tracer::ring(lines)),
+                    None => println!("--trace-ring expects a line count"),
+                }
+            }
+            "--cycle-rate" => {
+                match args.next().and_then(|rate| rate.parse().ok()) {
+                    Some(rate) => cycle_rate = Some(rate),
+                    None => println!("--cycle-rate expects a rate in Hz"),
+                }
+            }
+            "--speed" => {
+                let speed = args.next().and_then(|speed| speed.trim_end_matches('x').parse().ok());
+
+                if !speed.is_some_and(|speed| app.set_speed(speed)) {
+                    println!("--speed expects one of 0.25, 0.5, 1, 2, 4 or 8");
+                }
+            }
+            "--quirks" => {
+                match args.next().and_then(|name| Some((name, Quirks::preset(name)?))) {
+                    Some(preset) => quirks = Some(preset),
+                    None => println!("--quirks expects one of modern, vip, chip48 or xochip"),
+                }
+            }
+            "--platform" => {
+                match args.next().map(String::as_str) {
+                    Some("auto") => app.set_platform(None),
+                    Some(name) if Platform::from_name(name).is_some() => app.set_platform(Platform::from_name(name)),
+                    _ => println!("--platform expects one of auto, chip8, vip, schip or xochip"),
+                }
+            }
+            "--autofire" => {
+                let keys: Option<Vec<u8>> = args.next()
+                    .and_then(|keys| keys.split(',').map(|key| u8::from_str_radix(key, 16).ok().filter(|&key| key < 16)).collect());
+
+                match keys {
+                    Some(keys) => keys.into_iter().for_each(|key| app.enable_autofire(key)),
+                    None => println!("--autofire expects a comma separated list of hex keys, e.g. 5,A"),
+                }
+            }
+            "--autofire-rate" => {
+                match args.next().and_then(|rate| rate.parse().ok()) {
+                    Some(rate) => app.set_autofire_rate(rate),
+                    None => println!("--autofire-rate expects a rate in Hz"),
+                }
+            }
+            "--stats" => app.set_show_stats(true),
+            "--keypad" => app.set_show_keypad(true),
+            "--coverage" => app.enable_coverage(),
+            "--profile" => app.set_profiler_enabled(true),
+            "--write-warnings" => app.set_write_warnings(true),
+            "--watch" => app.set_watch_rom(true),
+            "--screen-size" => {
+                let size = args.next().and_then(|size| size.split_once('x'))
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+
+                match size {
+                    Some((width, height)) => app.set_screen_size(width, height),
+                    None => println!("--screen-size expects WIDTHxHEIGHT"),
+                }
+            }
+            "--filter" => {
+                let Some(names) = args.next() else {
+                    println!("--filter expects a comma separated list of filters");
+                    continue;
+                };
+
+                for name in names.split(',') {
+                    match Filter::from_name(name) {
+                        Some(filter) => app.toggle_filter(filter),
+                        None => println!("Unknown filter {}", name),
+                    }
+                }
+            }
+            _ if !arg.starts_with("--") => rom = Some(arg),
+            _ => println!("Unknown argument {}", arg),
+        }
+    }
+
+    // The ROM is opened last so an explicit --cycle-rate or --quirks wins over the detected platform.
+    if let Some(path) = rom {
+        if !app.open_rom(PathBuf::from(path)) {
+            println!("Failed to load {}", path);
+        }
+    }
+    if let Some(rate) = cycle_rate {
+        app.set_cycle_rate(rate);
+    }
+    if let Some((name, preset)) = quirks {
+        app.set_quirks(name, preset);
+    }
+
+    app.run();
+}
@@ -0,0 +1,203 @@
+use crate::chip_core::ChipCore;
+use crate::text;
+use crate::octo::Program;
+use minifb::{ Key, KeyRepeat, MouseButton, MouseMode, Scale, Window, WindowOptions };
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Follow {
+    Pc,
+    IReg,
+    Free,
+}
+
+pub struct MemoryViewer {
+    window: Window,
+    buf: Vec<u32>,
+    prev_ram: Vec<u8>,
+    changed: Vec<bool>,
+    follow: Follow,
+    top_row: usize,
+    selected: Option<usize>,
+    pending_nibble: Option<u8>,
+}
+
+impl MemoryViewer {
+    const BYTES_PER_ROW: usize = 16;
+    const TOTAL_ROWS: usize = ChipCore::RAM_SIZE / Self::BYTES_PER_ROW;
+    const VISIBLE_ROWS: usize = 32;
+    const HEADER_ROWS: usize = 2;
+    const ADDR_CHARS: usize = 5;
+    const WIDTH: usize = (Self::ADDR_CHARS + Self::BYTES_PER_ROW * 3) * text::CHAR_ADVANCE + 2;
+    const HEIGHT: usize = (Self::HEADER_ROWS + Self::VISIBLE_ROWS) * text::LINE_HEIGHT + 2;
+
+    const TEXT_COLOR: u32 = 0xC0C0C0;
+    const ADDR_COLOR: u32 = 0x808080;
+    const CHANGED_COLOR: u32 = 0xFF5050;
+    const PC_BG_COLOR: u32 = 0x205020;
+    const I_BG_COLOR: u32 = 0x202060;
+    const SELECTED_BG_COLOR: u32 = 0x806020;
+
+    const HEX_KEYS: [Key; 16] = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7,
+        Key::Key8, Key::Key9, Key::A, Key::B, Key::C, Key::D, Key::E, Key::F,
+    ];
+
+    pub fn new(chip: &ChipCore) -> minifb::Result<Self> {
+        let options = WindowOptions { scale: Scale::X2, ..WindowOptions::default() };
+
+        Ok(Self {
+            window: Window::new("Memory (Tab: follow PC/I, click + hex keys to edit while paused)", Self::WIDTH, Self::HEIGHT, options)?,
+            buf: vec![0; Self::WIDTH * Self::HEIGHT],
+            prev_ram: chip.ram().to_vec(),
+            changed: vec![false; ChipCore::RAM_SIZE],
+            follow: Follow::Pc,
+            top_row: 0,
+            selected: None,
+            pending_nibble: None,
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn scroll_to(&mut self, addr: usize) {
+        let row = (addr & 0xFFF) / Self::BYTES_PER_ROW;
+
+        if row < self.top_row || row >= self.top_row + Self::VISIBLE_ROWS {
+            self.top_row = row.saturating_sub(Self::VISIBLE_ROWS / 2).min(Self::TOTAL_ROWS - Self::VISIBLE_ROWS);
+        }
+    }
+
+    fn byte_at(&self, x: f32, y: f32) -> Option<usize> {
+        let col = (x as usize / text::CHAR_ADVANCE).checked_sub(Self::ADDR_CHARS)? / 3;
+        let row = (y as usize / text::LINE_HEIGHT).checked_sub(Self::HEADER_ROWS)?;
+
+        if col < Self::BYTES_PER_ROW && row < Self::VISIBLE_ROWS {
+            Some((self.top_row + row) * Self::BYTES_PER_ROW + col)
+        }
+        else {
+            None
+        }
+    }
+
+    fn handle_input(&mut self, chip: &mut ChipCore, paused: bool) {
+        if self.window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+            self.follow = if self.follow == Follow::Pc { Follow::IReg } else { Follow::Pc };
+        }
+
+        let scroll = if self.window.is_key_pressed(Key::Up, KeyRepeat::Yes) { -1 }
+            else if self.window.is_key_pressed(Key::Down, KeyRepeat::Yes) { 1 }
+            else if self.window.is_key_pressed(Key::PageUp, KeyRepeat::Yes) { -(Self::VISIBLE_ROWS as isize) }
+            else if self.window.is_key_pressed(Key::PageDown, KeyRepeat::Yes) { Self::VISIBLE_ROWS as isize }
+            else { self.window.get_scroll_wheel().map_or(0, |(_, dy)| -dy.signum() as isize) };
+
+        if scroll != 0 {
+            self.follow = Follow::Free;
+            self.top_row = self.top_row.saturating_add_signed(scroll).min(Self::TOTAL_ROWS - Self::VISIBLE_ROWS);
+        }
+
+        if self.window.get_mouse_down(MouseButton::Left) {
+            if let Some((x, y)) = self.window.get_mouse_pos(MouseMode::Discard) {
+                self.selected = self.byte_at(x, y);
+                self.pending_nibble = None;
+            }
+        }
+
+        let Some(addr) = self.selected else {
+            return;
+        };
+
+        if self.window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+            self.selected = None;
+            self.pending_nibble = None;
+            return;
+        }
+        if self.window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+            self.selected = Some((addr + 1) & 0xFFF);
+            self.pending_nibble = None;
+        }
+        else if self.window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+            self.selected = Some(addr.wrapping_sub(1) & 0xFFF);
+            self.pending_nibble = None;
+        }
+
+        if !paused {
+            return;
+        }
+
+        if let Some(nibble) = Self::HEX_KEYS.iter().position(|&key| self.window.is_key_pressed(key, KeyRepeat::No)) {
+            match self.pending_nibble.take() {
+                None => self.pending_nibble = Some(nibble as u8),
+                Some(high) => {
+                    chip.poke(addr as u16, (high << 4) | nibble as u8);
+                    self.selected = Some((addr + 1) & 0xFFF);
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, chip: &ChipCore, paused: bool, labels: &[(String, u16)]) {
+        self.buf.fill(0);
+
+        let pc = chip.pc() as usize & 0xFFF;
+        let i_reg = chip.i_reg() as usize & 0xFFF;
+        let follow = match self.follow {
+            Follow::Pc => "PC",
+            Follow::IReg => "I",
+            Follow::Free => "-",
+        };
+        let location = match Program::label_for(labels, pc as u16) {
+            Some((name, 0)) => format!("({})", name),
+            Some((name, offset)) => format!("({}+{})", name, offset),
+            None => String::new(),
+        };
+        let header = format!("PC:{:03X}{} I:{:03X} FOLLOW:{} {}", pc, location, i_reg, follow, if paused { "PAUSED" } else { "" });
+        text::draw_text(&mut self.buf, Self::WIDTH, 1, 1, &header, Self::TEXT_COLOR, 1);
+
+        for row in 0..Self::VISIBLE_ROWS {
+            let row_addr = (self.top_row + row) * Self::BYTES_PER_ROW;
+            let y = 1 + (Self::HEADER_ROWS + row) * text::LINE_HEIGHT;
+            text::draw_text(&mut self.buf, Self::WIDTH, 1, y, &format!("{:03X}", row_addr), Self::ADDR_COLOR, 1);
+
+            for col in 0..Self::BYTES_PER_ROW {
+                let addr = row_addr + col;
+                let x = 1 + (Self::ADDR_CHARS + col * 3) * text::CHAR_ADVANCE;
+
+                let background = if self.selected == Some(addr) { Some(Self::SELECTED_BG_COLOR) }
+                    else if addr == pc || addr == (pc + 1) & 0xFFF { Some(Self::PC_BG_COLOR) }
+                    else if addr == i_reg { Some(Self::I_BG_COLOR) }
+                    else { None };
+
+                if let Some(color) = background {
+                    text::fill_rect(&mut self.buf, Self::WIDTH, x - 1, y - 1, text::CHAR_ADVANCE * 2 + 1, text::LINE_HEIGHT + 1, color);
+                }
+
+                let value = match self.pending_nibble {
+                    Some(high) if self.selected == Some(addr) => format!("{:X}_", high),
+                    _ => format!("{:02X}", chip.ram()[addr]),
+                };
+                let color = if self.changed[addr] { Self::CHANGED_COLOR } else { Self::TEXT_COLOR };
+                text::draw_text(&mut self.buf, Self::WIDTH, x, y, &value, color, 1);
+            }
+        }
+    }
+
+    pub fn update(&mut self, chip: &mut ChipCore, paused: bool, labels: &[(String, u16)]) {
+        for (addr, &byte) in chip.ram().iter().enumerate() {
+            self.changed[addr] = byte != self.prev_ram[addr];
+        }
+        self.prev_ram.copy_from_slice(chip.ram());
+
+        self.handle_input(chip, paused);
+
+        match self.follow {
+            Follow::Pc => self.scroll_to(chip.pc() as usize),
+            Follow::IReg => self.scroll_to(chip.i_reg() as usize),
+            Follow::Free => {}
+        }
+
+        self.render(chip, paused, labels);
+        self.window.update_with_buffer(&self.buf, Self::WIDTH, Self::HEIGHT).unwrap();
+    }
+}
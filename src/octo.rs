@@ -0,0 +1,584 @@
+use std::collections::HashMap;
+use std::fmt;
+
+// A compiler for the core subset of Octo assembly: labels, :const, :alias, :org, :byte,
+// the register/ALU/memory statements and if/then, if/begin/else/end and loop/while/again.
+// Macros and :calc are not supported.
+pub struct Program {
+    pub bytes: Vec<u8>,
+    pub labels: Vec<(String, u16)>,
+}
+
+#[derive(Debug)]
+pub struct CompileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Program {
+    pub fn label_for(labels: &[(String, u16)], addr: u16) -> Option<(&str, u16)> {
+        labels.iter()
+            .filter(|(_, label_addr)| *label_addr <= addr)
+            .max_by_key(|(_, label_addr)| *label_addr)
+            .map(|(name, label_addr)| (name.as_str(), addr - label_addr))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Operand {
+    Reg(u16),
+    Imm(u16),
+}
+
+#[derive(Clone, Copy)]
+enum Cond {
+    Eq(u16, Operand),
+    Ne(u16, Operand),
+    Key(u16),
+    NotKey(u16),
+}
+
+impl Cond {
+    fn negate(self) -> Self {
+        match self {
+            Cond::Eq(x, rhs) => Cond::Ne(x, rhs),
+            Cond::Ne(x, rhs) => Cond::Eq(x, rhs),
+            Cond::Key(x) => Cond::NotKey(x),
+            Cond::NotKey(x) => Cond::Key(x),
+        }
+    }
+
+    // The opcode that skips the next instruction when the condition holds.
+    fn skip_opcode(self) -> u16 {
+        match self {
+            Cond::Eq(x, Operand::Imm(n)) => 0x3000 | (x << 8) | (n & 0xFF),
+            Cond::Eq(x, Operand::Reg(y)) => 0x5000 | (x << 8) | (y << 4),
+            Cond::Ne(x, Operand::Imm(n)) => 0x4000 | (x << 8) | (n & 0xFF),
+            Cond::Ne(x, Operand::Reg(y)) => 0x9000 | (x << 8) | (y << 4),
+            Cond::Key(x) => 0xE09E | (x << 8),
+            Cond::NotKey(x) => 0xE0A1 | (x << 8),
+        }
+    }
+}
+
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+struct Compiler<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    rom: Vec<u8>,
+    here: usize,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, u16>,
+    aliases: HashMap<String, u16>,
+    fixups: Vec<(usize, String, usize)>,
+    loops: Vec<(usize, Vec<usize>)>,
+    branches: Vec<usize>,
+}
+
+const START_ADDR: usize = 0x200;
+const END_ADDR: usize = 0x1000;
+
+fn parse_number(text: &str) -> Option<i32> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i32::from_str_radix(hex, 16).ok()?
+    }
+    else if let Some(bin) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        i32::from_str_radix(bin, 2).ok()?
+    }
+    else {
+        digits.parse::<i32>().ok()?
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+fn parse_register(text: &str) -> Option<u16> {
+    let digit = text.strip_prefix('v').or_else(|| text.strip_prefix('V'))?;
+
+    if digit.len() == 1 {
+        u16::from_str_radix(digit, 16).ok()
+    }
+    else {
+        None
+    }
+}
+
+impl<'a> Compiler<'a> {
+    fn new(source: &'a str) -> Self {
+        let tokens = source.lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                line.split('#').next().unwrap_or("")
+                    .split_whitespace()
+                    .map(move |text| Token { text, line: i + 1 })
+            })
+            .collect();
+
+        Self {
+            tokens,
+            pos: 0,
+            rom: Vec::new(),
+            here: START_ADDR,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            fixups: Vec::new(),
+            loops: Vec::new(),
+            branches: Vec::new(),
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos.saturating_sub(1)).map_or(0, |token| token.line)
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, CompileError> {
+        Err(CompileError { line: self.line(), message })
+    }
+
+    fn next(&mut self) -> Result<&'a str, CompileError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token.text)
+            }
+            None => self.error("unexpected end of file".to_string()),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|token| token.text)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), CompileError> {
+        let token = self.next()?;
+
+        if token == expected {
+            Ok(())
+        }
+        else {
+            self.error(format!("expected '{}', found '{}'", expected, token))
+        }
+    }
+
+    fn emit_byte(&mut self, byte: u8) -> Result<(), CompileError> {
+        if self.here >= END_ADDR {
+            return self.error("program does not fit in memory".to_string());
+        }
+
+        let offset = self.here - START_ADDR;
+        if self.rom.len() <= offset {
+            self.rom.resize(offset + 1, 0);
+        }
+
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn emit(&mut self, opcode: u16) -> Result<(), CompileError> {
+        self.emit_byte((opcode >> 8) as u8)?;
+        self.emit_byte(opcode as u8)
+    }
+
+    fn patch_addr(&mut self, at: usize, addr: usize) {
+        let offset = at - START_ADDR;
+        self.rom[offset] = (self.rom[offset] & 0xF0) | ((addr >> 8) & 0xF) as u8;
+        self.rom[offset + 1] = addr as u8;
+    }
+
+    fn register(&mut self) -> Result<u16, CompileError> {
+        let token = self.next()?;
+
+        match parse_register(token).or_else(|| self.aliases.get(token).copied()) {
+            Some(reg) => Ok(reg),
+            None => self.error(format!("expected a register, found '{}'", token)),
+        }
+    }
+
+    fn is_register(&self, token: &str) -> bool {
+        parse_register(token).is_some() || self.aliases.contains_key(token)
+    }
+
+    fn value(&mut self) -> Result<u16, CompileError> {
+        let token = self.next()?;
+
+        match parse_number(token) {
+            Some(value) if (-128..=0xFFFF).contains(&value) => Ok(value as u16),
+            Some(_) => self.error(format!("value '{}' out of range", token)),
+            None => match self.constants.get(token).or_else(|| self.labels.get(token)) {
+                Some(&value) => Ok(value),
+                None => self.error(format!("unknown constant '{}'", token)),
+            },
+        }
+    }
+
+    fn byte_value(&mut self) -> Result<u16, CompileError> {
+        let value = self.value()?;
+
+        if value <= 0xFF || value >= 0xFF80 {
+            Ok(value & 0xFF)
+        }
+        else {
+            self.error(format!("value 0x{:X} does not fit in a byte", value))
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, CompileError> {
+        match self.peek() {
+            Some(token) if self.is_register(token) => Ok(Operand::Reg(self.register()?)),
+            _ => Ok(Operand::Imm(self.byte_value()?)),
+        }
+    }
+
+    // Emits `opcode` with its low 12 bits set to the address, resolving labels defined later.
+    fn emit_addr_op(&mut self, opcode: u16) -> Result<(), CompileError> {
+        let token = self.next()?;
+
+        let addr = match parse_number(token) {
+            Some(value) if (0..0x1000).contains(&value) => value as u16,
+            Some(_) => return self.error(format!("address '{}' out of range", token)),
+            None => match self.constants.get(token).or_else(|| self.labels.get(token)) {
+                Some(&addr) => addr,
+                None => {
+                    self.fixups.push((self.here, token.to_string(), self.line()));
+                    0
+                }
+            },
+        };
+
+        self.emit(opcode | (addr & 0xFFF))
+    }
+
+    fn condition(&mut self) -> Result<Cond, CompileError> {
+        let x = self.register()?;
+        let op = self.next()?;
+
+        match op {
+            "key" => return Ok(Cond::Key(x)),
+            "-key" => return Ok(Cond::NotKey(x)),
+            _ => {}
+        }
+
+        let rhs = self.operand()?;
+
+        match op {
+            "==" => Ok(Cond::Eq(x, rhs)),
+            "!=" => Ok(Cond::Ne(x, rhs)),
+            "<" | ">" | "<=" | ">=" => {
+                // Like Octo, comparisons are computed into vF: vF = (lhs >= rhs) after the subtraction.
+                let (lhs, rhs) = if op == "<" || op == ">=" { (Operand::Reg(x), rhs) } else { (rhs, Operand::Reg(x)) };
+
+                match (lhs, rhs) {
+                    (lhs, Operand::Reg(y)) => {
+                        match lhs {
+                            Operand::Reg(r) => self.emit(0x8F00 | (r << 4))?,
+                            Operand::Imm(n) => self.emit(0x6F00 | n)?,
+                        }
+                        self.emit(0x8F05 | (y << 4))?;
+                    }
+                    (Operand::Reg(r), Operand::Imm(n)) => {
+                        self.emit(0x6F00 | n)?;
+                        self.emit(0x8F07 | (r << 4))?;
+                    }
+                    (Operand::Imm(_), Operand::Imm(_)) => unreachable!(),
+                }
+
+                if op == "<" || op == ">" {
+                    Ok(Cond::Eq(0xF, Operand::Imm(0)))
+                }
+                else {
+                    Ok(Cond::Ne(0xF, Operand::Imm(0)))
+                }
+            }
+            _ => self.error(format!("unknown comparison '{}'", op)),
+        }
+    }
+
+    fn register_statement(&mut self, x: u16) -> Result<(), CompileError> {
+        let op = self.next()?;
+
+        if op == ":=" {
+            match self.peek() {
+                Some("random") => {
+                    self.next()?;
+                    let mask = self.byte_value()?;
+                    return self.emit(0xC000 | (x << 8) | mask);
+                }
+                Some("key") => {
+                    self.next()?;
+                    return self.emit(0xF00A | (x << 8));
+                }
+                Some("delay") => {
+                    self.next()?;
+                    return self.emit(0xF007 | (x << 8));
+                }
+                _ => {}
+            }
+        }
+
+        let rhs = self.operand()?;
+
+        match (op, rhs) {
+            (":=", Operand::Imm(n)) => self.emit(0x6000 | (x << 8) | n),
+            (":=", Operand::Reg(y)) => self.emit(0x8000 | (x << 8) | (y << 4)),
+            ("+=", Operand::Imm(n)) => self.emit(0x7000 | (x << 8) | n),
+            ("+=", Operand::Reg(y)) => self.emit(0x8004 | (x << 8) | (y << 4)),
+            ("-=", Operand::Imm(n)) => self.emit(0x7000 | (x << 8) | (n.wrapping_neg() & 0xFF)),
+            ("-=", Operand::Reg(y)) => self.emit(0x8005 | (x << 8) | (y << 4)),
+            ("=-", Operand::Reg(y)) => self.emit(0x8007 | (x << 8) | (y << 4)),
+            ("|=", Operand::Reg(y)) => self.emit(0x8001 | (x << 8) | (y << 4)),
+            ("&=", Operand::Reg(y)) => self.emit(0x8002 | (x << 8) | (y << 4)),
+            ("^=", Operand::Reg(y)) => self.emit(0x8003 | (x << 8) | (y << 4)),
+            (">>=", Operand::Reg(y)) => self.emit(0x8006 | (x << 8) | (y << 4)),
+            ("<<=", Operand::Reg(y)) => self.emit(0x800E | (x << 8) | (y << 4)),
+            _ => self.error(format!("invalid operands for '{}'", op)),
+        }
+    }
+
+    fn i_statement(&mut self) -> Result<(), CompileError> {
+        match self.next()? {
+            ":=" => {
+                match self.peek() {
+                    Some("hex") => {
+                        self.next()?;
+                        let x = self.register()?;
+                        self.emit(0xF029 | (x << 8))
+                    }
+                    Some("bighex") => {
+                        self.next()?;
+                        let x = self.register()?;
+                        self.emit(0xF030 | (x << 8))
+                    }
+                    _ => self.emit_addr_op(0xA000),
+                }
+            }
+            "+=" => {
+                let x = self.register()?;
+                self.emit(0xF01E | (x << 8))
+            }
+            op => self.error(format!("invalid operator '{}' for i", op)),
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), CompileError> {
+        let token = self.next()?;
+
+        if let Some(value) = parse_number(token) {
+            if !(-128..=0xFF).contains(&value) {
+                return self.error(format!("byte '{}' out of range", token));
+            }
+            return self.emit_byte(value as u8);
+        }
+
+        match token {
+            ":" => {
+                let name = self.next()?;
+                if self.labels.insert(name.to_string(), self.here as u16).is_some() {
+                    return self.error(format!("label '{}' is already defined", name));
+                }
+                Ok(())
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.value()?;
+                self.constants.insert(name.to_string(), value);
+                Ok(())
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let reg = self.register()?;
+                self.aliases.insert(name.to_string(), reg);
+                Ok(())
+            }
+            ":org" => {
+                let addr = self.value()? as usize;
+                if !(START_ADDR..END_ADDR).contains(&addr) {
+                    return self.error(format!("org address 0x{:X} out of range", addr));
+                }
+                self.here = addr;
+                Ok(())
+            }
+            ":byte" => {
+                let value = self.byte_value()?;
+                self.emit_byte(value as u8)
+            }
+            ":call" => self.emit_addr_op(0x2000),
+            ":breakpoint" => self.next().map(|_| ()),
+            ":monitor" => {
+                self.next()?;
+                self.next().map(|_| ())
+            }
+            "clear" => self.emit(0x00E0),
+            "return" | ";" => self.emit(0x00EE),
+            "hires" => self.emit(0x00FF),
+            "lores" => self.emit(0x00FE),
+            "exit" => self.emit(0x00FD),
+            "scroll-left" => self.emit(0x00FC),
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-down" => {
+                let n = self.value()?;
+                if n > 0xF {
+                    return self.error("scroll-down amount must be 0-15".to_string());
+                }
+                self.emit(0x00C0 | n)
+            }
+            "jump" => self.emit_addr_op(0x1000),
+            "jump0" => self.emit_addr_op(0xB000),
+            "native" => self.emit_addr_op(0x0000),
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.value()?;
+                if n > 0xF {
+                    return self.error("sprite height must be 0-15".to_string());
+                }
+                self.emit(0xD000 | (x << 8) | (y << 4) | n)
+            }
+            "bcd" => { let x = self.register()?; self.emit(0xF033 | (x << 8)) }
+            "save" => { let x = self.register()?; self.emit(0xF055 | (x << 8)) }
+            "load" => { let x = self.register()?; self.emit(0xF065 | (x << 8)) }
+            "saveflags" => { let x = self.register()?; self.emit(0xF075 | (x << 8)) }
+            "loadflags" => { let x = self.register()?; self.emit(0xF085 | (x << 8)) }
+            "delay" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(0xF015 | (x << 8))
+            }
+            "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(0xF018 | (x << 8))
+            }
+            "i" => self.i_statement(),
+            "if" => {
+                let cond = self.condition()?;
+
+                match self.next()? {
+                    "then" => {
+                        self.emit(cond.negate().skip_opcode())?;
+                        self.statement()
+                    }
+                    "begin" => {
+                        self.emit(cond.skip_opcode())?;
+                        self.branches.push(self.here);
+                        self.emit(0x1000)
+                    }
+                    other => self.error(format!("expected 'then' or 'begin', found '{}'", other)),
+                }
+            }
+            "else" => {
+                let Some(jump) = self.branches.pop() else {
+                    return self.error("'else' without 'if ... begin'".to_string());
+                };
+
+                let end_jump = self.here;
+                self.emit(0x1000)?;
+                self.patch_addr(jump, self.here);
+                self.branches.push(end_jump);
+                Ok(())
+            }
+            "end" => {
+                let Some(jump) = self.branches.pop() else {
+                    return self.error("'end' without 'if ... begin'".to_string());
+                };
+
+                self.patch_addr(jump, self.here);
+                Ok(())
+            }
+            "loop" => {
+                self.loops.push((self.here, Vec::new()));
+                Ok(())
+            }
+            "while" => {
+                let cond = self.condition()?;
+                let Some((_, breaks)) = self.loops.last_mut() else {
+                    return self.error("'while' outside of a loop".to_string());
+                };
+
+                breaks.push(self.here + 2);
+                self.emit(cond.skip_opcode())?;
+                self.emit(0x1000)
+            }
+            "again" => {
+                let Some((start, breaks)) = self.loops.pop() else {
+                    return self.error("'again' without 'loop'".to_string());
+                };
+
+                self.emit(0x1000 | start as u16)?;
+                for jump in breaks {
+                    self.patch_addr(jump, self.here);
+                }
+                Ok(())
+            }
+            ":macro" | ":calc" | ":stringmode" | ":assert" | ":unpack" | ":next" => {
+                self.error(format!("'{}' is not supported", token))
+            }
+            _ if self.is_register(token) => {
+                let x = parse_register(token).or_else(|| self.aliases.get(token).copied()).unwrap();
+                self.register_statement(x)
+            }
+            _ if token.starts_with(':') => self.error(format!("unknown directive '{}'", token)),
+            _ => {
+                // A bare name calls the label, which may be defined further down.
+                self.pos -= 1;
+                self.emit_addr_op(0x2000)
+            }
+        }
+    }
+
+    fn compile(mut self) -> Result<Program, CompileError> {
+        // Execution starts at 0x200, which always holds a jump to ': main'.
+        self.emit(0x1000)?;
+
+        while self.pos < self.tokens.len() {
+            self.statement()?;
+        }
+
+        if !self.branches.is_empty() {
+            return self.error("'if ... begin' without 'end'".to_string());
+        }
+        if !self.loops.is_empty() {
+            return self.error("'loop' without 'again'".to_string());
+        }
+
+        match self.labels.get("main") {
+            Some(&main) => self.patch_addr(START_ADDR, main as usize),
+            None => return Err(CompileError { line: 1, message: "missing ': main' label".to_string() }),
+        }
+
+        for (at, name, line) in std::mem::take(&mut self.fixups) {
+            match self.labels.get(&name) {
+                Some(&addr) => self.patch_addr(at, addr as usize),
+                None => return Err(CompileError { line, message: format!("undefined label '{}'", name) }),
+            }
+        }
+
+        let mut labels: Vec<(String, u16)> = self.labels.into_iter().collect();
+        labels.sort_by_key(|(_, addr)| *addr);
+
+        Ok(Program { bytes: self.rom, labels })
+    }
+}
+
+pub fn compile(source: &str) -> Result<Program, CompileError> {
+    Compiler::new(source).compile()
+}
+
+#[cfg(test)]
+mod tests;
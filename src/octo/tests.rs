@@ -0,0 +1,85 @@
+use super::*;
+
+fn bytes(source: &str) -> Vec<u8> {
+    match compile(source) {
+        Ok(program) => program.bytes,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn error(source: &str) -> String {
+    match compile(source) {
+        Ok(_) => panic!("expected a compile error"),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn starts_with_jump_to_main() {
+    let program = compile(": main clear").unwrap();
+    assert_eq!(program.bytes, [0x12, 0x02, 0x00, 0xE0]);
+    assert_eq!(program.labels, [("main".to_string(), 0x202)]);
+
+    // main doesn't have to come first.
+    assert_eq!(bytes(": sub return : main sub"), [0x12, 0x04, 0x00, 0xEE, 0x22, 0x02]);
+}
+
+#[test]
+fn resolves_forward_references() {
+    assert_eq!(bytes(": main jump done : done return"), [0x12, 0x02, 0x12, 0x04, 0x00, 0xEE]);
+    assert_eq!(bytes(": main sub : sub ;"), [0x12, 0x02, 0x22, 0x04, 0x00, 0xEE]);
+    assert_eq!(bytes(": main i := data : data 0x3C"), [0x12, 0x02, 0xA2, 0x04, 0x3C]);
+}
+
+#[test]
+fn constants_and_aliases() {
+    let source = ":const speed 3\n:alias px v2\n: main\n px := speed\n px += 1\n px -= 1\n vf := px";
+    assert_eq!(bytes(source), [0x12, 0x02, 0x62, 0x03, 0x72, 0x01, 0x72, 0xFF, 0x8F, 0x20]);
+}
+
+#[test]
+fn if_then_skips_over_the_statement() {
+    assert_eq!(bytes(": main if v1 == 5 then v2 := 1"), [0x12, 0x02, 0x41, 0x05, 0x62, 0x01]);
+    assert_eq!(bytes(": main if v1 != v3 then v2 := 1"), [0x12, 0x02, 0x51, 0x30, 0x62, 0x01]);
+    assert_eq!(bytes(": main if v1 key then v2 := 1"), [0x12, 0x02, 0xE1, 0xA1, 0x62, 0x01]);
+    assert_eq!(bytes(": main if v1 -key then v2 := 1"), [0x12, 0x02, 0xE1, 0x9E, 0x62, 0x01]);
+}
+
+#[test]
+fn comparisons_are_computed_into_vf() {
+    // vF := v1, vF -= v2 leaves vF = 0 exactly when v1 < v2.
+    assert_eq!(bytes(": main if v1 < v2 then clear"), [0x12, 0x02, 0x8F, 0x10, 0x8F, 0x25, 0x4F, 0x00, 0x00, 0xE0]);
+    assert_eq!(bytes(": main if v1 >= v2 then clear"), [0x12, 0x02, 0x8F, 0x10, 0x8F, 0x25, 0x3F, 0x00, 0x00, 0xE0]);
+    // vF := 5, vF =- v1 leaves vF = 0 exactly when v1 < 5.
+    assert_eq!(bytes(": main if v1 < 5 then clear"), [0x12, 0x02, 0x6F, 0x05, 0x8F, 0x17, 0x4F, 0x00, 0x00, 0xE0]);
+    assert_eq!(bytes(": main if v1 > 5 then clear"), [0x12, 0x02, 0x6F, 0x05, 0x8F, 0x15, 0x4F, 0x00, 0x00, 0xE0]);
+}
+
+#[test]
+fn if_begin_else_end() {
+    let source = ": main if v0 == 1 begin v1 := 2 else v1 := 3 end";
+    assert_eq!(bytes(source), [0x12, 0x02, 0x30, 0x01, 0x12, 0x0A, 0x61, 0x02, 0x12, 0x0C, 0x61, 0x03]);
+}
+
+#[test]
+fn loop_while_again() {
+    let source = ": main loop v0 += 1 while v0 != 5 again";
+    assert_eq!(bytes(source), [0x12, 0x02, 0x70, 0x01, 0x40, 0x05, 0x12, 0x0A, 0x12, 0x02]);
+}
+
+#[test]
+fn org_and_bytes() {
+    assert_eq!(bytes(": main return :org 0x206 :byte 0xAB -1"), [0x12, 0x02, 0x00, 0xEE, 0x00, 0x00, 0xAB, 0xFF]);
+}
+
+#[test]
+fn errors_report_their_line() {
+    assert_eq!(error("clear"), "line 1: missing ': main' label");
+    assert_eq!(error(": main\n\njump nowhere"), "line 3: undefined label 'nowhere'");
+    assert_eq!(error(": main\nv0 := 0x1FF"), "line 2: value 0x1FF does not fit in a byte");
+    assert_eq!(error(": main\n: main"), "line 2: label 'main' is already defined");
+    assert_eq!(error(": main\n:macro foo"), "line 2: ':macro' is not supported");
+    assert_eq!(error(": main\nloop\nclear"), "line 3: 'loop' without 'again'");
+    assert_eq!(error(": main\nif v0 == 1 begin\nelse\nelse\nend\nend"), "line 6: 'end' without 'if ... begin'");
+    assert_eq!(error(": main # comment\nsprite v0 v1 16"), "line 2: sprite height must be 0-15");
+}
@@ -0,0 +1,113 @@
+use crate::text;
+use std::collections::VecDeque;
+use std::time::{ Duration, Instant };
+
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    pub fps: f64,
+    pub ipf: f64,
+    pub mips: f64,
+    pub frame_time_ms: f64,
+}
+
+// Text overlays drawn straight into the output buffer: transient messages in the bottom
+// left, an optional stats HUD in the top left and a paused indicator in the top right.
+pub struct Osd {
+    messages: VecDeque<(String, Instant)>,
+    show_stats: bool,
+    stats: Stats,
+    sound_active: bool,
+    key_wait_reg: Option<u8>,
+}
+
+impl Osd {
+    const MESSAGE_DURATION: Duration = Duration::from_secs(2);
+    const MAX_MESSAGES: usize = 4;
+    const TEXT_COLOR: u32 = 0xFFFFFF;
+    const WARNING_COLOR: u32 = 0xFFD040;
+    const BACKGROUND_COLOR: u32 = 0x202020;
+    const MARGIN: usize = 2;
+
+    pub fn new() -> Self {
+        Self { messages: VecDeque::new(), show_stats: false, stats: Stats::default(), sound_active: false, key_wait_reg: None }
+    }
+
+    pub fn notify(&mut self, message: &str) {
+        if self.messages.len() == Self::MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((message.to_string(), Instant::now()));
+    }
+
+    pub fn show_stats(&self) -> bool {
+        self.show_stats
+    }
+
+    pub fn set_show_stats(&mut self, show: bool) {
+        self.show_stats = show;
+    }
+
+    pub fn set_stats(&mut self, stats: Stats) {
+        self.stats = stats;
+    }
+
+    pub fn set_sound_active(&mut self, active: bool) {
+        self.sound_active = active;
+    }
+
+    // Register FX0A is waiting to store a key in, if any.
+    pub fn set_key_wait(&mut self, reg: Option<u8>) {
+        self.key_wait_reg = reg;
+    }
+
+    pub fn update(&mut self) {
+        self.messages.retain(|(_, shown)| shown.elapsed() < Self::MESSAGE_DURATION);
+    }
+
+    // Text scale that keeps the 3x5 font readable without covering too much of the game.
+    fn scale_for(height: usize) -> usize {
+        (height / 240).max(1)
+    }
+
+    fn draw_label(buf: &mut [u32], width: usize, x: usize, y: usize, label: &str, color: u32, scale: usize) {
+        let margin = Self::MARGIN * scale;
+        let w = text::text_width(label, scale) + margin * 2;
+        let h = text::GLYPH_HEIGHT * scale + margin * 2;
+
+        text::fill_rect(buf, width, x, y, w, h, Self::BACKGROUND_COLOR);
+        text::draw_text(buf, width, x + margin, y + margin, label, color, scale);
+    }
+
+    pub fn draw(&self, buf: &mut [u32], width: usize, height: usize, paused: bool) {
+        let scale = Self::scale_for(height);
+        let line_height = (text::GLYPH_HEIGHT + Self::MARGIN * 2 + 1) * scale;
+        let edge = Self::MARGIN * scale;
+
+        if self.show_stats {
+            let lines = [
+                format!("FPS {:.1}", self.stats.fps),
+                format!("IPF {:.1}", self.stats.ipf),
+                format!("MIPS {:.3}", self.stats.mips),
+                format!("FRAME {:.2} MS", self.stats.frame_time_ms),
+                format!("SOUND {}", if self.sound_active { "ON" } else { "OFF" }),
+                format!("KEY WAIT {}", self.key_wait_reg.map_or("-".to_string(), |reg| format!("V{:X}", reg))),
+            ];
+
+            for (i, line) in lines.iter().enumerate() {
+                Self::draw_label(buf, width, edge, edge + i * line_height, line, Self::TEXT_COLOR, scale);
+            }
+        }
+
+        if paused {
+            let label = "PAUSED";
+            let label_width = text::text_width(label, scale) + Self::MARGIN * 2 * scale;
+            Self::draw_label(buf, width, width.saturating_sub(label_width + edge), edge, label, Self::WARNING_COLOR, scale);
+        }
+
+        let mut y = height.saturating_sub(edge);
+        for (message, _) in self.messages.iter().rev() {
+            y = y.saturating_sub(line_height);
+            Self::draw_label(buf, width, edge, y, message, Self::TEXT_COLOR, scale);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::thread;
+use std::time::{ Duration, Instant };
+
+// Schedules fixed-rate emulation ticks from a monotonic clock, independently of how often
+// the frontend manages to present.
+pub struct FramePacer {
+    tick_length: Duration,
+    next_tick: Instant,
+    dropped_ticks: u64,
+}
+
+impl FramePacer {
+    // Past this many ticks behind, the backlog is dropped instead of fast-forwarded through.
+    const MAX_CATCH_UP: u32 = 4;
+
+    pub fn new(rate_hz: u32) -> Self {
+        Self {
+            tick_length: Duration::from_secs(1) / rate_hz,
+            next_tick: Instant::now(),
+            dropped_ticks: 0,
+        }
+    }
+
+    // Number of ticks that are due now, at most MAX_CATCH_UP.
+    pub fn due_ticks(&mut self) -> u32 {
+        let now = Instant::now();
+        let mut ticks = 0;
+
+        while self.next_tick <= now {
+            if ticks == Self::MAX_CATCH_UP {
+                let behind = now.duration_since(self.next_tick).as_nanos() / self.tick_length.as_nanos();
+                self.dropped_ticks += behind as u64 + 1;
+                self.next_tick = now + self.tick_length;
+                break;
+            }
+
+            ticks += 1;
+            self.next_tick += self.tick_length;
+        }
+
+        ticks
+    }
+
+    // Forgets any backlog, e.g. after the loop was blocked in a file dialog.
+    pub fn resync(&mut self) {
+        self.next_tick = Instant::now();
+    }
+
+    pub fn wait_for_next_tick(&self) {
+        let now = Instant::now();
+
+        if self.next_tick > now {
+            thread::sleep(self.next_tick - now);
+        }
+    }
+
+    pub fn take_dropped_ticks(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped_ticks)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::analyzer::Analysis;
+use crate::chip_core::ChipCore;
+use crate::quirks::Quirks;
+
+// The interpreter a ROM was written for, guessed from the opcodes it can reach.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Platform {
+    // Plain CHIP-8 calling 0NNN machine code routines, which only ran on the VIP.
+    Vip,
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+pub struct Detection {
+    pub platform: Platform,
+    // The first opcode that gave the platform away, as (address, opcode).
+    pub evidence: Option<(u16, u16)>,
+}
+
+impl Detection {
+    pub fn describe(&self) -> String {
+        match self.evidence {
+            Some((addr, opcode)) => format!("{} ({:04X} at {:03X})", self.platform.name(), opcode, addr),
+            None => self.platform.name().to_string(),
+        }
+    }
+}
+
+impl Platform {
+    pub const ALL: [Platform; 4] = [Platform::Chip8, Platform::Vip, Platform::SuperChip, Platform::XoChip];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Vip => "COSMAC VIP",
+            Platform::Chip8 => "CHIP-8",
+            Platform::SuperChip => "SuperChip",
+            Platform::XoChip => "XO-CHIP",
+        }
+    }
+
+    fn short_name(self) -> &'static str {
+        match self {
+            Platform::Vip => "vip",
+            Platform::Chip8 => "chip8",
+            Platform::SuperChip => "schip",
+            Platform::XoChip => "xochip",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.replace(['-', '_', ' '], "");
+        Self::ALL.into_iter().find(|platform| {
+            platform.short_name().eq_ignore_ascii_case(&name) || platform.name().replace(['-', ' '], "").eq_ignore_ascii_case(&name)
+        })
+    }
+
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Platform::Vip => Quirks::VIP,
+            Platform::Chip8 => Quirks::MODERN,
+            Platform::SuperChip => Quirks::CHIP48,
+            Platform::XoChip => Quirks::XOCHIP,
+        }
+    }
+
+    // 11, 30 and 1000 instructions per 60 Hz frame, the usual defaults for each platform.
+    pub fn cycle_rate(self) -> u32 {
+        match self {
+            Platform::Vip | Platform::Chip8 => ChipCore::DEFAULT_CYCLE_RATE,
+            Platform::SuperChip => 1800,
+            Platform::XoChip => 60000,
+        }
+    }
+
+    fn is_schip_opcode(opcode: u16) -> bool {
+        matches!(opcode, 0x00FB..=0x00FF) || opcode & 0xFFF0 == 0x00C0
+            || (opcode & 0xF000 == 0xF000 && matches!(opcode & 0x00FF, 0x30 | 0x75 | 0x85))
+    }
+
+    fn is_xochip_opcode(opcode: u16) -> bool {
+        opcode == 0xF000 || opcode == 0xF002 || opcode & 0xF0FF == 0xF001 || opcode & 0xF0FF == 0xF03A
+            || (opcode & 0xF000 == 0x5000 && matches!(opcode & 0x000F, 0x2 | 0x3))
+    }
+
+    // Only opcodes reachable from 0x200 are looked at, so sprite data can't cause false positives.
+    // XO-CHIP wins over SuperChip, which wins over VIP machine code calls.
+    pub fn detect(rom: &[u8]) -> Detection {
+        let analysis = Analysis::new(rom);
+        let unknown: Vec<(u16, u16)> = analysis.unknown_opcodes().collect();
+
+        if let Some(&evidence) = unknown.iter().find(|(_, opcode)| Self::is_xochip_opcode(*opcode)) {
+            return Detection { platform: Platform::XoChip, evidence: Some(evidence) };
+        }
+        // The core doesn't implement every SuperChip opcode, so some only show up as unknown ones.
+        let schip = analysis.instructions().chain(unknown.iter().copied())
+            .filter(|&(_, opcode)| Self::is_schip_opcode(opcode))
+            .min_by_key(|&(addr, _)| addr);
+        if let Some(evidence) = schip {
+            return Detection { platform: Platform::SuperChip, evidence: Some(evidence) };
+        }
+        if let Some(&evidence) = unknown.iter().find(|(_, opcode)| opcode & 0xF000 == 0 && opcode & 0x0FFF != 0) {
+            return Detection { platform: Platform::Vip, evidence: Some(evidence) };
+        }
+
+        Detection { platform: Platform::Chip8, evidence: None }
+    }
+}
+
+#[cfg(test)]
+mod tests;
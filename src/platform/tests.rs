@@ -0,0 +1,51 @@
+use super::*;
+
+fn rom(words: &[u16]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_be_bytes()).collect()
+}
+
+#[test]
+fn plain_rom_is_chip8() {
+    let detection = Platform::detect(&rom(&[0x00E0, 0xA208, 0xD015, 0x1206]));
+    assert_eq!(detection.platform, Platform::Chip8);
+    assert_eq!(detection.evidence, None);
+}
+
+#[test]
+fn detects_superchip_opcodes() {
+    for opcode in [0x00FF, 0x00C4, 0xF130, 0xF275, 0x00FD, 0xF385] {
+        let detection = Platform::detect(&rom(&[0x6000, opcode, 0x1204]));
+        assert_eq!(detection.platform, Platform::SuperChip, "{:04X}", opcode);
+        assert_eq!(detection.evidence, Some((0x202, opcode)));
+    }
+}
+
+#[test]
+fn detects_xochip_opcodes() {
+    for opcode in [0xF000, 0x5122, 0xF201] {
+        let detection = Platform::detect(&rom(&[0x00FF, opcode, 0x1204]));
+        assert_eq!(detection.platform, Platform::XoChip, "{:04X}", opcode);
+    }
+}
+
+#[test]
+fn detects_vip_machine_code_calls() {
+    let detection = Platform::detect(&rom(&[0x6000, 0x0123]));
+    assert_eq!((detection.platform, detection.evidence), (Platform::Vip, Some((0x202, 0x0123))));
+}
+
+#[test]
+fn ignores_opcodes_in_unreachable_data() {
+    // 00FF and F000 only appear as sprite rows after the jump.
+    let detection = Platform::detect(&rom(&[0xA206, 0x1202, 0xF000, 0x00FF]));
+    assert_eq!(detection.platform, Platform::Chip8);
+}
+
+#[test]
+fn platforms_by_name() {
+    assert_eq!(Platform::from_name("schip"), Some(Platform::SuperChip));
+    assert_eq!(Platform::from_name("XO-CHIP"), Some(Platform::XoChip));
+    assert_eq!(Platform::from_name("chip8"), Some(Platform::Chip8));
+    assert_eq!(Platform::from_name("cosmac vip"), Some(Platform::Vip));
+    assert_eq!(Platform::from_name("nes"), None);
+}
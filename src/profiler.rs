@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OpClass {
+    Draw,
+    Screen,
+    Alu,
+    Load,
+    Jump,
+    Call,
+    Skip,
+    KeyWait,
+    Memory,
+    Timer,
+    Other,
+}
+
+impl OpClass {
+    const COUNT: usize = 11;
+    const ALL: [OpClass; Self::COUNT] = [
+        OpClass::Draw, OpClass::Screen, OpClass::Alu, OpClass::Load, OpClass::Jump, OpClass::Call,
+        OpClass::Skip, OpClass::KeyWait, OpClass::Memory, OpClass::Timer, OpClass::Other,
+    ];
+
+    pub fn of(opcode: u16) -> Self {
+        match opcode & 0xF000 {
+            0x0000 => {
+                match opcode {
+                    0x00EE => OpClass::Call,
+                    0x00E0 | 0x00FB | 0x00FC | 0x00FE | 0x00FF => OpClass::Screen,
+                    _ if opcode & 0xFFF0 == 0x00C0 => OpClass::Screen,
+                    _ => OpClass::Other,
+                }
+            }
+            0x1000 | 0xB000 => OpClass::Jump,
+            0x2000 => OpClass::Call,
+            0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xE000 => OpClass::Skip,
+            0x6000 | 0xA000 => OpClass::Load,
+            0x7000 | 0x8000 | 0xC000 => OpClass::Alu,
+            0xD000 => OpClass::Draw,
+            0xF000 => {
+                match opcode & 0x00FF {
+                    0x0A => OpClass::KeyWait,
+                    0x07 | 0x15 | 0x18 => OpClass::Timer,
+                    0x1E | 0x29 | 0x30 => OpClass::Load,
+                    0x33 | 0x55 | 0x65 | 0x75 | 0x85 => OpClass::Memory,
+                    _ => OpClass::Other,
+                }
+            }
+            _ => OpClass::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OpClass::Draw => "draw",
+            OpClass::Screen => "screen",
+            OpClass::Alu => "alu",
+            OpClass::Load => "load",
+            OpClass::Jump => "jump",
+            OpClass::Call => "call/ret",
+            OpClass::Skip => "skip",
+            OpClass::KeyWait => "key wait",
+            OpClass::Memory => "memory",
+            OpClass::Timer => "timer",
+            OpClass::Other => "other",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct RoutineStats {
+    calls: u64,
+    self_cycles: u64,
+    // Cycles of finished visits. A routine still on the stack adds the cycles since `entered_at`.
+    total_cycles: u64,
+    // Recursive routines appear on the stack more than once but should only be counted once.
+    depth: u32,
+    entered_at: u64,
+}
+
+pub struct Profiler {
+    class_counts: [u64; OpClass::COUNT],
+    call_stack: Vec<u16>,
+    routines: HashMap<u16, RoutineStats>,
+    total_cycles: u64,
+}
+
+impl Profiler {
+    const ROOT_ROUTINE: u16 = 0x200;
+    const HOTTEST_COUNT: usize = 8;
+
+    pub fn new() -> Self {
+        let mut profiler = Self {
+            class_counts: [0; OpClass::COUNT],
+            call_stack: vec![Self::ROOT_ROUTINE],
+            routines: HashMap::new(),
+            total_cycles: 0,
+        };
+        profiler.enter(Self::ROOT_ROUTINE);
+        profiler
+    }
+
+    fn enter(&mut self, routine: u16) {
+        let stats = self.routines.entry(routine).or_default();
+        if stats.depth == 0 {
+            stats.entered_at = self.total_cycles;
+        }
+        stats.depth += 1;
+    }
+
+    fn leave(&mut self, routine: u16) {
+        let stats = self.routines.entry(routine).or_default();
+        stats.depth -= 1;
+        if stats.depth == 0 {
+            stats.total_cycles += self.total_cycles - stats.entered_at;
+        }
+    }
+
+    fn total_cycles_of(&self, stats: &RoutineStats) -> u64 {
+        stats.total_cycles + if stats.depth > 0 { self.total_cycles - stats.entered_at } else { 0 }
+    }
+
+    pub fn record(&mut self, opcode: u16) {
+        let class = OpClass::of(opcode);
+        self.class_counts[class as usize] += 1;
+        self.total_cycles += 1;
+
+        let current = *self.call_stack.last().unwrap();
+        self.routines.entry(current).or_default().self_cycles += 1;
+
+        if opcode & 0xF000 == 0x2000 {
+            let target = opcode & 0x0FFF;
+            self.routines.entry(target).or_default().calls += 1;
+
+            // The core's stack wraps at 16 entries, keep ours bounded the same way.
+            if self.call_stack.len() > 16 {
+                let dropped = self.call_stack.remove(1);
+                self.leave(dropped);
+            }
+            self.call_stack.push(target);
+            self.enter(target);
+        }
+        else if opcode == 0x00EE && self.call_stack.len() > 1 {
+            let returned = self.call_stack.pop().unwrap();
+            self.leave(returned);
+        }
+    }
+
+    // Called when the core resets, so the next instruction counts towards the root again.
+    pub fn reset_call_stack(&mut self) {
+        while self.call_stack.len() > 1 {
+            let routine = self.call_stack.pop().unwrap();
+            self.leave(routine);
+        }
+    }
+
+    pub fn reset_counts(&mut self) {
+        self.class_counts = [0; OpClass::COUNT];
+        self.routines.clear();
+        self.total_cycles = 0;
+
+        for routine in self.call_stack.clone() {
+            self.enter(routine);
+        }
+    }
+
+    pub fn report(&self) -> String {
+        if self.total_cycles == 0 {
+            return "No instructions executed".to_string();
+        }
+
+        let percent = |count: u64| count as f64 * 100.0 / self.total_cycles as f64;
+        let mut report = format!("{} instructions:", self.total_cycles);
+
+        for class in OpClass::ALL {
+            let count = self.class_counts[class as usize];
+            if count > 0 {
+                report += &format!(" {} {:.1}%", class.name(), percent(count));
+            }
+        }
+
+        let mut hottest: Vec<(u16, u64, &RoutineStats)> = self.routines.iter()
+            .map(|(&addr, stats)| (addr, self.total_cycles_of(stats), stats))
+            .collect();
+        hottest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        report += "\n  routine    total    self   calls";
+        for (addr, total_cycles, stats) in hottest.into_iter().take(Self::HOTTEST_COUNT) {
+            report += &format!("\n  {:03X}     {:5.1}%  {:5.1}%  {:6}", addr, percent(total_cycles), percent(stats.self_cycles), stats.calls);
+        }
+
+        report
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,58 @@
+use super::*;
+
+fn profile(opcodes: &[u16]) -> Profiler {
+    let mut profiler = Profiler::new();
+    for &opcode in opcodes {
+        profiler.record(opcode);
+    }
+    profiler
+}
+
+// (self, total) cycles of `routine`.
+fn cycles(profiler: &Profiler, routine: u16) -> (u64, u64) {
+    let stats = &profiler.routines[&routine];
+    (stats.self_cycles, profiler.total_cycles_of(stats))
+}
+
+#[test]
+fn totals_include_called_routines() {
+    let profiler = profile(&[0x2300, 0x6001, 0x2400, 0x6001, 0x00EE, 0x00EE, 0x6001]);
+
+    assert_eq!(cycles(&profiler, 0x200), (2, 7));
+    assert_eq!(cycles(&profiler, 0x300), (3, 5));
+    assert_eq!(cycles(&profiler, 0x400), (2, 2));
+    assert_eq!(profiler.routines[&0x400].calls, 1);
+}
+
+#[test]
+fn recursion_is_counted_once() {
+    let profiler = profile(&[0x2300, 0x2300, 0x00EE, 0x00EE, 0x6001]);
+
+    assert_eq!(cycles(&profiler, 0x300), (3, 3));
+    assert_eq!(profiler.routines[&0x300].calls, 2);
+}
+
+#[test]
+fn routines_on_the_stack_survive_a_count_reset() {
+    let mut profiler = profile(&[0x2300, 0x6001]);
+    assert_eq!(cycles(&profiler, 0x300), (1, 1));
+
+    profiler.reset_counts();
+    profiler.record(0x6001);
+    assert_eq!(cycles(&profiler, 0x300), (1, 1));
+    assert_eq!(cycles(&profiler, 0x200), (0, 1));
+
+    profiler.reset_call_stack();
+    profiler.record(0x6001);
+    assert_eq!(cycles(&profiler, 0x300), (1, 1));
+    assert_eq!(cycles(&profiler, 0x200), (1, 2));
+}
+
+#[test]
+fn call_stack_is_bounded_like_the_core() {
+    let profiler = profile(&[0x2300; 40]);
+
+    assert_eq!(profiler.call_stack.len(), 17);
+    assert_eq!(profiler.routines[&0x300].depth, 16);
+    assert_eq!(cycles(&profiler, 0x300), (39, 39));
+}
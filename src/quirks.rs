@@ -0,0 +1,77 @@
+// Behaviour that differs between the CHIP-8 interpreters ROMs were written for.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyWaitMode {
+    // FX0A completes once a key is pressed and released again (COSMAC VIP, Octo).
+    Release,
+    // FX0A completes as soon as a key is pressed (CHIP-48).
+    Press,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Quirks {
+    pub key_wait: KeyWaitMode,
+    // The VIP's FX0A routine sounds the buzzer for as long as the key is held.
+    pub vip_buzzer: bool,
+    // 8XY1, 8XY2 and 8XY3 clear VF.
+    pub vf_reset: bool,
+    // 8XY6 and 8XYE shift VY into VX instead of shifting VX in place.
+    pub shift_vy: bool,
+    // FX55 and FX65 leave I pointing past the last register they touched.
+    pub memory_increment: bool,
+    // BNNN jumps to NNN + VX, with X taken from the top nibble of NNN.
+    pub jump_vx: bool,
+}
+
+impl Quirks {
+    pub const MODERN: Quirks = Quirks {
+        key_wait: KeyWaitMode::Release,
+        vip_buzzer: false,
+        vf_reset: true,
+        shift_vy: false,
+        memory_increment: false,
+        jump_vx: false,
+    };
+    pub const VIP: Quirks = Quirks {
+        key_wait: KeyWaitMode::Release,
+        vip_buzzer: true,
+        vf_reset: true,
+        shift_vy: true,
+        memory_increment: true,
+        jump_vx: false,
+    };
+    pub const CHIP48: Quirks = Quirks {
+        key_wait: KeyWaitMode::Press,
+        vip_buzzer: false,
+        vf_reset: false,
+        shift_vy: false,
+        memory_increment: false,
+        jump_vx: true,
+    };
+    pub const XOCHIP: Quirks = Quirks {
+        key_wait: KeyWaitMode::Release,
+        vip_buzzer: false,
+        vf_reset: false,
+        shift_vy: true,
+        memory_increment: true,
+        jump_vx: false,
+    };
+
+    pub const PRESETS: [(&'static str, Quirks); 4] = [
+        ("Modern", Self::MODERN),
+        ("VIP", Self::VIP),
+        ("CHIP-48", Self::CHIP48),
+        ("XO-CHIP", Self::XOCHIP),
+    ];
+
+    pub fn preset(name: &str) -> Option<Quirks> {
+        let name = name.replace('-', "");
+        Self::PRESETS.iter().find(|(preset, _)| preset.replace('-', "").eq_ignore_ascii_case(&name)).map(|&(_, quirks)| quirks)
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::MODERN
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{ Hash, Hasher };
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, Instant, SystemTime };
+
+pub struct RomWatcher {
+    path: PathBuf,
+    mtime: Option<SystemTime>,
+    hash: Option<u64>,
+    last_check: Instant,
+}
+
+impl RomWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            mtime: Self::mtime(path),
+            hash: Self::hash(path),
+            last_check: Instant::now(),
+        }
+    }
+
+    fn mtime(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    fn hash(path: &Path) -> Option<u64> {
+        let bytes = fs::read(path).ok()?;
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    // Returns true once per change to the file's mtime or contents. A file that
+    // disappears (e.g. while an assembler rewrites it) is not reported until it is back.
+    pub fn poll(&mut self) -> bool {
+        if self.last_check.elapsed() < Self::POLL_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let mtime = Self::mtime(&self.path);
+        let hash = Self::hash(&self.path);
+
+        if hash.is_none() || (mtime == self.mtime && hash == self.hash) {
+            return false;
+        }
+
+        self.mtime = mtime;
+        self.hash = hash;
+        true
+    }
+}
+
+pub fn is_octo_source(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("8o"))
+}
+
+// The file the frontend runs and reloads, with the watcher that follows it. The watcher is
+// always created from the path that actually loaded, so a failed open leaves both alone.
+pub struct WatchedRom {
+    path: PathBuf,
+    watcher: Option<RomWatcher>,
+    watch: bool,
+    has_rom: bool,
+}
+
+impl WatchedRom {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, watcher: None, watch: false, has_rom: false }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watch
+    }
+
+    // Octo source is always watched so edits are recompiled, the setting only covers binary ROMs.
+    pub fn set_watch(&mut self, watch: bool) {
+        self.watch = watch;
+
+        if self.has_rom && !is_octo_source(&self.path) {
+            self.watcher = if watch { Some(RomWatcher::new(&self.path)) } else { None };
+        }
+    }
+
+    // Records the outcome of loading `path`. Only a ROM that loaded becomes current, except Octo
+    // source, which stays current after a failed compile so saving a fix recompiles it.
+    pub fn finish_load(&mut self, path: PathBuf, loaded: bool) {
+        let is_octo_source = is_octo_source(&path);
+        if !loaded && !is_octo_source {
+            return;
+        }
+
+        self.watcher = if self.watch || is_octo_source { Some(RomWatcher::new(&path)) } else { None };
+        self.path = path;
+        self.has_rom = true;
+    }
+
+    pub fn poll(&mut self) -> bool {
+        self.watcher.as_mut().is_some_and(|watcher| watcher.poll())
+    }
+}
+
+#[cfg(test)]
+mod tests;
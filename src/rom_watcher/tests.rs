@@ -0,0 +1,68 @@
+use super::*;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("chiprust8-watch-{}-{}", std::process::id(), name))
+}
+
+// Changes the file's contents and lets the next poll through without waiting.
+fn change(rom: &mut WatchedRom, path: &Path, contents: &[u8]) {
+    fs::write(path, contents).unwrap();
+    if let Some(watcher) = &mut rom.watcher {
+        watcher.last_check = Instant::now() - RomWatcher::POLL_INTERVAL;
+    }
+}
+
+#[test]
+fn failed_open_keeps_watching_the_loaded_rom() {
+    let loaded = temp_path("loaded.ch8");
+    let missing = temp_path("missing.ch8");
+    fs::write(&loaded, [0x12, 0x00]).unwrap();
+
+    let mut rom = WatchedRom::new(std::env::temp_dir());
+    rom.set_watch(true);
+    rom.finish_load(loaded.clone(), true);
+    rom.finish_load(missing, false);
+    assert_eq!(rom.path(), loaded);
+
+    change(&mut rom, &loaded, &[0x12, 0x02]);
+    assert!(rom.poll());
+    assert_eq!(rom.path(), loaded);
+
+    fs::remove_file(&loaded).unwrap();
+}
+
+#[test]
+fn failed_octo_compile_stays_current_and_watched() {
+    let loaded = temp_path("octo-loaded.ch8");
+    let source = temp_path("broken.8o");
+    fs::write(&loaded, [0x12, 0x00]).unwrap();
+    fs::write(&source, ": main jump").unwrap();
+
+    let mut rom = WatchedRom::new(std::env::temp_dir());
+    rom.finish_load(loaded.clone(), true);
+    rom.finish_load(source.clone(), false);
+    assert_eq!(rom.path(), source);
+
+    change(&mut rom, &source, b": main jump main");
+    assert!(rom.poll());
+
+    fs::remove_file(&loaded).unwrap();
+    fs::remove_file(&source).unwrap();
+}
+
+#[test]
+fn binary_roms_are_only_watched_in_watch_mode() {
+    let path = temp_path("unwatched.ch8");
+    fs::write(&path, [0x12, 0x00]).unwrap();
+
+    let mut rom = WatchedRom::new(std::env::temp_dir());
+    rom.finish_load(path.clone(), true);
+    change(&mut rom, &path, &[0x12, 0x02]);
+    assert!(!rom.poll());
+
+    rom.set_watch(true);
+    change(&mut rom, &path, &[0x12, 0x04]);
+    assert!(rom.poll());
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,146 @@
+use crate::chip_core::ChipCore;
+use crate::text;
+use minifb::{ Key, KeyRepeat, Scale, Window, WindowOptions };
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SpriteMode {
+    Chip { height: usize },
+    Schip,
+}
+
+impl SpriteMode {
+    fn width(self) -> usize {
+        match self {
+            SpriteMode::Chip { .. } => 8,
+            SpriteMode::Schip => 16,
+        }
+    }
+    fn height(self) -> usize {
+        match self {
+            SpriteMode::Chip { height } => height,
+            SpriteMode::Schip => 16,
+        }
+    }
+    fn size_bytes(self) -> usize {
+        self.height() * self.width() / 8
+    }
+}
+
+pub struct SpriteViewer {
+    window: Window,
+    buf: Vec<u32>,
+    mode: SpriteMode,
+    cursor: usize,
+    follow_i: bool,
+}
+
+impl SpriteViewer {
+    const COLUMNS: usize = 8;
+    const ROWS: usize = 6;
+    const CELL_SIZE: usize = 16 + 2;
+    const HEADER_HEIGHT: usize = text::LINE_HEIGHT + 2;
+    const WIDTH: usize = Self::COLUMNS * Self::CELL_SIZE;
+    const HEIGHT: usize = Self::HEADER_HEIGHT + Self::ROWS * Self::CELL_SIZE;
+
+    const TEXT_COLOR: u32 = 0xC0C0C0;
+    const CELL_COLOR: u32 = 0x202020;
+    const CURSOR_COLOR: u32 = 0x605020;
+    const PIXEL_COLOR: u32 = 0xFFFFFF;
+
+    pub fn new(chip: &ChipCore) -> minifb::Result<Self> {
+        let options = WindowOptions { scale: Scale::X4, ..WindowOptions::default() };
+
+        Ok(Self {
+            window: Window::new("Sprites (arrows: move, +/-: height, M: 8xN/16x16, Tab: follow I)", Self::WIDTH, Self::HEIGHT, options)?,
+            buf: vec![0; Self::WIDTH * Self::HEIGHT],
+            mode: SpriteMode::Chip { height: 8 },
+            cursor: chip.i_reg() as usize & 0xFFF,
+            follow_i: true,
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn handle_input(&mut self) {
+        let step = self.mode.size_bytes() as isize;
+        let page = step * (Self::COLUMNS * Self::ROWS) as isize;
+
+        let delta = if self.window.is_key_pressed(Key::Right, KeyRepeat::Yes) { 1 }
+            else if self.window.is_key_pressed(Key::Left, KeyRepeat::Yes) { -1 }
+            else if self.window.is_key_pressed(Key::Down, KeyRepeat::Yes) { step }
+            else if self.window.is_key_pressed(Key::Up, KeyRepeat::Yes) { -step }
+            else if self.window.is_key_pressed(Key::PageDown, KeyRepeat::Yes) { page }
+            else if self.window.is_key_pressed(Key::PageUp, KeyRepeat::Yes) { -page }
+            else { 0 };
+
+        if delta != 0 {
+            self.follow_i = false;
+            self.cursor = self.cursor.wrapping_add_signed(delta) & 0xFFF;
+        }
+
+        if self.window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+            self.follow_i = !self.follow_i;
+        }
+        if self.window.is_key_pressed(Key::M, KeyRepeat::No) {
+            self.mode = if self.mode == SpriteMode::Schip { SpriteMode::Chip { height: 8 } } else { SpriteMode::Schip };
+        }
+
+        if let SpriteMode::Chip { height } = &mut self.mode {
+            if self.window.is_key_pressed(Key::Equal, KeyRepeat::Yes) || self.window.is_key_pressed(Key::NumPadPlus, KeyRepeat::Yes) {
+                *height = (*height + 1).min(15);
+            }
+            else if self.window.is_key_pressed(Key::Minus, KeyRepeat::Yes) || self.window.is_key_pressed(Key::NumPadMinus, KeyRepeat::Yes) {
+                *height = (*height - 1).max(1);
+            }
+        }
+    }
+
+    fn render_sprite(&mut self, ram: &[u8], addr: usize, x: usize, y: usize) {
+        let bytes_per_row = self.mode.width() / 8;
+
+        for row in 0..self.mode.height() {
+            for byte in 0..bytes_per_row {
+                let bits = ram[(addr + row * bytes_per_row + byte) & 0xFFF];
+
+                for bit in 0..8 {
+                    if (bits >> (7 - bit)) & 0x1 == 1 {
+                        self.buf[(y + row) * Self::WIDTH + x + byte * 8 + bit] = Self::PIXEL_COLOR;
+                    }
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, chip: &ChipCore) {
+        self.buf.fill(0);
+
+        let size = match self.mode {
+            SpriteMode::Chip { height } => format!("8X{}", height),
+            SpriteMode::Schip => "16X16".to_string(),
+        };
+        let header = format!("{:03X} {} {}", self.cursor, size, if self.follow_i { "FOLLOW I" } else { "" });
+        text::draw_text(&mut self.buf, Self::WIDTH, 1, 1, &header, Self::TEXT_COLOR, 1);
+
+        for index in 0..Self::COLUMNS * Self::ROWS {
+            let x = (index % Self::COLUMNS) * Self::CELL_SIZE;
+            let y = Self::HEADER_HEIGHT + (index / Self::COLUMNS) * Self::CELL_SIZE;
+            let color = if index == 0 { Self::CURSOR_COLOR } else { Self::CELL_COLOR };
+
+            text::fill_rect(&mut self.buf, Self::WIDTH, x, y, Self::CELL_SIZE - 1, Self::CELL_SIZE - 1, color);
+            self.render_sprite(chip.ram(), self.cursor + index * self.mode.size_bytes(), x + 1, y + 1);
+        }
+    }
+
+    pub fn update(&mut self, chip: &ChipCore) {
+        self.handle_input();
+
+        if self.follow_i {
+            self.cursor = chip.i_reg() as usize & 0xFFF;
+        }
+
+        self.render(chip);
+        self.window.update_with_buffer(&self.buf, Self::WIDTH, Self::HEIGHT).unwrap();
+    }
+}
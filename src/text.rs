@@ -0,0 +1,109 @@
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+pub const CHAR_ADVANCE: usize = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 1;
+
+// 3x5 glyphs for ' '..='_', three bits per row with the leftmost pixel in bit 2.
+// Lowercase letters are drawn with the uppercase glyphs.
+const FONT: [[u8; GLYPH_HEIGHT]; 64] = [
+    [0, 0, 0, 0, 0], // ' '
+    [2, 2, 2, 0, 2], // !
+    [5, 5, 0, 0, 0], // "
+    [5, 7, 5, 7, 5], // #
+    [3, 6, 2, 3, 6], // $
+    [5, 1, 2, 4, 5], // %
+    [2, 5, 2, 5, 3], // &
+    [2, 2, 0, 0, 0], // '
+    [1, 2, 2, 2, 1], // (
+    [4, 2, 2, 2, 4], // )
+    [0, 5, 2, 5, 0], // *
+    [0, 2, 7, 2, 0], // +
+    [0, 0, 0, 2, 4], // ,
+    [0, 0, 7, 0, 0], // -
+    [0, 0, 0, 0, 2], // .
+    [1, 1, 2, 4, 4], // /
+    [7, 5, 5, 5, 7], // 0
+    [2, 6, 2, 2, 7], // 1
+    [7, 1, 7, 4, 7], // 2
+    [7, 1, 3, 1, 7], // 3
+    [5, 5, 7, 1, 1], // 4
+    [7, 4, 7, 1, 7], // 5
+    [7, 4, 7, 5, 7], // 6
+    [7, 1, 1, 2, 2], // 7
+    [7, 5, 7, 5, 7], // 8
+    [7, 5, 7, 1, 7], // 9
+    [0, 2, 0, 2, 0], // :
+    [0, 2, 0, 2, 4], // ;
+    [1, 2, 4, 2, 1], // <
+    [0, 7, 0, 7, 0], // =
+    [4, 2, 1, 2, 4], // >
+    [7, 1, 3, 0, 2], // ?
+    [2, 5, 7, 4, 3], // @
+    [2, 5, 7, 5, 5], // A
+    [6, 5, 6, 5, 6], // B
+    [3, 4, 4, 4, 3], // C
+    [6, 5, 5, 5, 6], // D
+    [7, 4, 6, 4, 7], // E
+    [7, 4, 6, 4, 4], // F
+    [3, 4, 5, 5, 3], // G
+    [5, 5, 7, 5, 5], // H
+    [7, 2, 2, 2, 7], // I
+    [1, 1, 1, 5, 2], // J
+    [5, 5, 6, 5, 5], // K
+    [4, 4, 4, 4, 7], // L
+    [5, 7, 7, 5, 5], // M
+    [6, 5, 5, 5, 5], // N
+    [2, 5, 5, 5, 2], // O
+    [6, 5, 6, 4, 4], // P
+    [2, 5, 5, 6, 3], // Q
+    [6, 5, 6, 5, 5], // R
+    [3, 4, 2, 1, 6], // S
+    [7, 2, 2, 2, 2], // T
+    [5, 5, 5, 5, 7], // U
+    [5, 5, 5, 5, 2], // V
+    [5, 5, 7, 7, 5], // W
+    [5, 5, 2, 5, 5], // X
+    [5, 5, 2, 2, 2], // Y
+    [7, 1, 2, 4, 7], // Z
+    [6, 4, 4, 4, 6], // [
+    [4, 4, 2, 1, 1], // \
+    [3, 1, 1, 1, 3], // ]
+    [2, 5, 0, 0, 0], // ^
+    [0, 0, 0, 0, 7], // _
+];
+
+pub fn fill_rect(buf: &mut [u32], buf_width: usize, x: usize, y: usize, w: usize, h: usize, color: u32) {
+    if buf_width == 0 {
+        return;
+    }
+    let buf_height = buf.len() / buf_width;
+
+    for row in y..(y + h).min(buf_height) {
+        for col in x..(x + w).min(buf_width) {
+            buf[row * buf_width + col] = color;
+        }
+    }
+}
+
+pub fn draw_text(buf: &mut [u32], buf_width: usize, x: usize, y: usize, text: &str, color: u32, scale: usize) {
+    for (i, c) in text.chars().enumerate() {
+        let c = c.to_ascii_uppercase();
+        let glyph = if (' '..='_').contains(&c) { &FONT[c as usize - ' ' as usize] } else { &FONT['?' as usize - ' ' as usize] };
+        let glyph_x = x + i * CHAR_ADVANCE * scale;
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if (bits >> (GLYPH_WIDTH - 1 - col)) & 0x1 == 1 {
+                    fill_rect(buf, buf_width, glyph_x + col * scale, y + row * scale, scale, scale, color);
+                }
+            }
+        }
+    }
+}
+
+pub fn text_width(text: &str, scale: usize) -> usize {
+    (text.chars().count() * CHAR_ADVANCE).saturating_sub(1) * scale
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,18 @@
+use super::*;
+
+#[test]
+fn fill_rect_clips_to_the_buffer() {
+    let mut buf = vec![0; 4 * 3];
+    fill_rect(&mut buf, 4, 2, 1, 10, 10, 7);
+
+    assert_eq!(buf, [0, 0, 0, 0, 0, 0, 7, 7, 0, 0, 7, 7]);
+}
+
+#[test]
+fn drawing_into_an_empty_buffer_does_nothing() {
+    let mut buf: Vec<u32> = Vec::new();
+    fill_rect(&mut buf, 0, 0, 0, 5, 5, 7);
+    draw_text(&mut buf, 0, 0, 0, "PAUSED", 7, 2);
+
+    assert!(buf.is_empty());
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+use std::path::Path;
+
+// One line per executed instruction, state captured before the instruction runs:
+// PC:0200 OP:00E0 V0:00 .. VF:00 I:0000 SP:00 DT:00 ST:00 ; CLS
+// Everything after ';' is informational and ignored by `diff_traces`.
+enum TraceSink {
+    File(BufWriter<File>),
+    Ring { lines: VecDeque<String>, capacity: usize },
+    // A file that failed to write, e.g. a full disk. The error is reported once, then tracing stops.
+    Failed,
+}
+
+pub struct Tracer {
+    sink: TraceSink,
+}
+
+pub struct TraceDivergence {
+    pub line: usize,
+    pub fields: Vec<(String, String, String)>,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl Tracer {
+    pub fn to_file(path: &Path) -> io::Result<Self> {
+        Ok(Self { sink: TraceSink::File(BufWriter::new(File::create(path)?)) })
+    }
+
+    pub fn ring(capacity: usize) -> Self {
+        Self { sink: TraceSink::Ring { lines: VecDeque::with_capacity(capacity), capacity: capacity.max(1) } }
+    }
+
+    pub fn record(&mut self, line: String) {
+        match &mut self.sink {
+            TraceSink::File(writer) => {
+                if let Err(e) = writeln!(writer, "{}", line) {
+                    println!("Failed to write trace, tracing stopped: {}", e);
+                    self.sink = TraceSink::Failed;
+                }
+            }
+            TraceSink::Ring { lines, capacity } => {
+                if lines.len() == *capacity {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+            TraceSink::Failed => {}
+        }
+    }
+
+    pub fn ring_lines(&self) -> impl Iterator<Item = &String> {
+        let lines = match &self.sink {
+            TraceSink::Ring { lines, .. } => Some(lines.iter()),
+            TraceSink::File(_) | TraceSink::Failed => None,
+        };
+        lines.into_iter().flatten()
+    }
+
+    pub fn flush(&mut self) {
+        if let TraceSink::File(writer) = &mut self.sink {
+            let _ = writer.flush();
+        }
+    }
+}
+
+fn parse_fields(line: &str) -> Vec<(&str, &str)> {
+    let state = line.split(';').next().unwrap_or("");
+
+    state.split_whitespace()
+        .filter_map(|field| field.split_once(':'))
+        .collect()
+}
+
+// Values are compared as hex numbers where possible, so "V0:5" matches "V0:05".
+fn values_match(left: &str, right: &str) -> bool {
+    match (u32::from_str_radix(left, 16), u32::from_str_radix(right, 16)) {
+        (Ok(l), Ok(r)) => l == r,
+        _ => left.eq_ignore_ascii_case(right),
+    }
+}
+
+// Only fields present in both lines are compared, so traces from emulators that
+// don't log e.g. SP or the timers can still be diffed against ours. A line pair with no
+// fields in common at all counts as a divergence, since nothing could be checked.
+pub fn diff_traces(left: &str, right: &str) -> Option<TraceDivergence> {
+    let mut left_lines = left.lines();
+    let mut right_lines = right.lines();
+    let mut line = 0;
+
+    loop {
+        line += 1;
+
+        match (left_lines.next(), right_lines.next()) {
+            (None, None) => return None,
+            (Some(l), Some(r)) => {
+                let right_fields = parse_fields(r);
+                let common: Vec<(&str, &str, &str)> = parse_fields(l).into_iter()
+                    .filter_map(|(key, l_val)| {
+                        let (_, r_val) = right_fields.iter().find(|(k, _)| k.eq_ignore_ascii_case(key))?;
+                        Some((key, l_val, *r_val))
+                    })
+                    .collect();
+
+                let fields: Vec<(String, String, String)> = common.iter()
+                    .filter(|(_, l_val, r_val)| !values_match(l_val, r_val))
+                    .map(|(key, l_val, r_val)| (key.to_string(), l_val.to_string(), r_val.to_string()))
+                    .collect();
+
+                if common.is_empty() || !fields.is_empty() {
+                    return Some(TraceDivergence { line, fields, left: Some(l.to_string()), right: Some(r.to_string()) });
+                }
+            }
+            (l, r) => {
+                return Some(TraceDivergence { line, fields: Vec::new(), left: l.map(str::to_string), right: r.map(str::to_string) });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
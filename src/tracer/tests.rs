@@ -0,0 +1,68 @@
+use super::*;
+
+const LEFT: &str = "PC:0200 OP:00E0 V0:00 I:0000 ; CLS\nPC:0202 OP:6005 V0:00 I:0000 ; LD V0, 05\nPC:0204 OP:A300 V0:05 I:0000 ; LD I, 300\n";
+
+#[test]
+fn identical_traces_match() {
+    assert!(diff_traces(LEFT, LEFT).is_none());
+}
+
+#[test]
+fn values_are_compared_as_hex() {
+    let right = "PC:200 OP:00e0 V0:0 I:0\nPC:202 OP:6005 V0:0 I:0\nPC:204 OP:a300 V0:5 I:0\n";
+    assert!(diff_traces(LEFT, right).is_none());
+}
+
+#[test]
+fn reports_first_divergent_field() {
+    let right = LEFT.replace("V0:05 I:0000", "V0:06 I:0000");
+    let div = diff_traces(LEFT, &right).unwrap();
+
+    assert_eq!(div.line, 3);
+    assert_eq!(div.fields, [("V0".to_string(), "05".to_string(), "06".to_string())]);
+}
+
+#[test]
+fn only_common_fields_are_compared() {
+    // The right trace doesn't log I, so the differing I values are not a divergence.
+    let right = "PC:0200 V0:00\nPC:0202 V0:00\nPC:0204 V0:05\n";
+    let left = LEFT.replace("I:0000 ; LD I", "I:0123 ; LD I");
+    assert!(diff_traces(&left, right).is_none());
+}
+
+#[test]
+fn lines_without_common_fields_diverge() {
+    let div = diff_traces("PC:0200 OP:00E0\n", "addr=0200 opcode=00E0\n").unwrap();
+
+    assert_eq!(div.line, 1);
+    assert!(div.fields.is_empty());
+    assert_eq!(div.right.as_deref(), Some("addr=0200 opcode=00E0"));
+}
+
+#[test]
+fn unequal_lengths_diverge_at_the_end() {
+    let shorter: String = LEFT.lines().take(2).map(|line| format!("{}\n", line)).collect();
+    let div = diff_traces(LEFT, &shorter).unwrap();
+
+    assert_eq!(div.line, 3);
+    assert!(div.fields.is_empty());
+    assert!(div.left.is_some() && div.right.is_none());
+}
+
+#[test]
+fn comments_after_semicolon_are_ignored() {
+    let right = LEFT.replace("; CLS", "; clear screen V0:FF");
+    assert!(diff_traces(LEFT, &right).is_none());
+}
+
+#[test]
+fn write_errors_stop_the_file_trace() {
+    let Ok(mut tracer) = Tracer::to_file(Path::new("/dev/full")) else {
+        return;
+    };
+
+    for _ in 0..10000 {
+        tracer.record("PC:0200 OP:00E0".to_string());
+    }
+    assert!(matches!(tracer.sink, TraceSink::Failed));
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeSet;
+use crate::chip_core::ChipCore;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WriteKind {
+    // Into an address that has already been executed as an instruction.
+    SelfModifying,
+    // Into the interpreter area below 0x200, where the fonts live.
+    Font,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WriteEvent {
+    pub pc: u16,
+    pub addr: u16,
+    pub kind: WriteKind,
+}
+
+impl WriteEvent {
+    pub fn describe(&self) -> String {
+        match self.kind {
+            WriteKind::SelfModifying => format!("Self-modifying write to {:03X} at PC {:03X}", self.addr, self.pc),
+            WriteKind::Font => format!("Write to font area {:03X} at PC {:03X}", self.addr, self.pc),
+        }
+    }
+}
+
+// Flags FX33/FX55 writes that hit code or the font area. Each PC and address pair is only
+// reported once, so a loop rewriting the same byte doesn't flood the log.
+pub struct WriteMonitor {
+    executed: Vec<bool>,
+    seen: BTreeSet<(u16, u16)>,
+    events: Vec<WriteEvent>,
+}
+
+impl WriteMonitor {
+    pub fn new() -> Self {
+        Self {
+            executed: vec![false; ChipCore::RAM_SIZE],
+            seen: BTreeSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record_exec(&mut self, addr: u16) {
+        self.executed[addr as usize & 0xFFF] = true;
+        self.executed[(addr as usize + 1) & 0xFFF] = true;
+    }
+
+    pub fn record_write(&mut self, pc: u16, addr: u16) {
+        let addr = addr & 0xFFF;
+        let kind = if addr < 0x200 {
+            WriteKind::Font
+        }
+        else if self.executed[addr as usize] {
+            WriteKind::SelfModifying
+        }
+        else {
+            return;
+        };
+
+        if self.seen.insert((pc, addr)) {
+            self.events.push(WriteEvent { pc, addr, kind });
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<WriteEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+impl Default for WriteMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}